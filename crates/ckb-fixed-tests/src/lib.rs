@@ -1,4 +1,7 @@
 pub use ckb_fixed;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use wasmer::{FunctionEnv, FunctionEnvMut, Memory};
 pub use wasmer::{Instance, Store, TypedFunction, Value};
 
 #[cfg(test)]
@@ -17,6 +20,25 @@ pub enum Error {
     Sin,
     Sqrt,
     Add,
+    SqrtPriceAtTick,
+    TickAtSqrtPrice,
+    NormalInvCdf,
+    Root,
+    Hypot,
+    FromNumber,
+    ToBigInt,
+    Batch,
+    Formula,
+}
+
+/// Host state the imports need after instantiation.
+struct Host {
+    memory: Option<Memory>,
+}
+
+thread_local! {
+    /// The contents `&mut [u8]` arguments copy back to JavaScript, by heap index.
+    static TYPED_ARRAYS: RefCell<HashMap<i32, Vec<u8>>> = RefCell::new(HashMap::new());
 }
 
 pub fn initialize_wasmer() -> (Store, Instance) {
@@ -25,6 +47,7 @@ pub fn initialize_wasmer() -> (Store, Instance) {
     let mut store = Store::default();
     let bytes = include_bytes!("../../ckb-fixed/pkg/ckb_fixed_bg.wasm");
     let module = Module::new(&store, bytes).unwrap();
+    let env = FunctionEnv::new(&mut store, Host { memory: None });
 
     // Create import object with required wasm-bindgen functions
     let import_object = imports! {
@@ -37,9 +60,50 @@ pub fn initialize_wasmer() -> (Store, Instance) {
                 &mut store,
                 |_ptr: i32, _len: i32| {}
             ),
+            // `bigint` support, only reached through `fromBits`, `toBits` and `fromBigInt`
+            "__wbindgen_bigint_from_i64" => wasmer::Function::new_typed(
+                &mut store,
+                |_n: i64| -> i32 { 0 }
+            ),
+            "__wbindgen_bigint_from_u64" => wasmer::Function::new_typed(
+                &mut store,
+                |_n: i64| -> i32 { 0 }
+            ),
+            "__wbindgen_bigint_from_i128" => wasmer::Function::new_typed(
+                &mut store,
+                |_hi: i64, _lo: i64| -> i32 { 0 }
+            ),
+            "__wbindgen_bigint_get_as_i64" => wasmer::Function::new_typed(
+                &mut store,
+                |_ret_ptr: i32, _idx: i32| {}
+            ),
+            "__wbindgen_shr" => wasmer::Function::new_typed(
+                &mut store,
+                |_a: i32, _b: i32| -> i32 { 0 }
+            ),
+            "__wbindgen_jsval_eq" => wasmer::Function::new_typed(
+                &mut store,
+                |_a: i32, _b: i32| -> i32 { 0 }
+            ),
+            "__wbindgen_object_drop_ref" => wasmer::Function::new_typed(
+                &mut store,
+                |_idx: i32| {}
+            ),
+            // copies `&mut [u8]` arguments of the batch functions back to JavaScript
+            "__wbindgen_copy_to_typed_array" => wasmer::Function::new_typed_with_env(
+                &mut store,
+                &env,
+                |env: FunctionEnvMut<Host>, ptr: i32, len: i32, idx: i32| {
+                    let memory = env.data().memory.as_ref().unwrap();
+                    let mut bytes = vec![0u8; len as usize];
+                    memory.view(&env).read(ptr as u64, &mut bytes).unwrap();
+                    TYPED_ARRAYS.with(|arrays| arrays.borrow_mut().insert(idx, bytes));
+                }
+            ),
         }
     };
     let instance = Instance::new(&mut store, &module, &import_object).unwrap();
+    env.as_mut(&mut store).memory = Some(instance.exports.get_memory("memory").unwrap().clone());
 
     (store, instance)
 }
@@ -74,7 +138,165 @@ pub fn i64f64_sqrt(store: &mut Store, instance: &Instance, a: i32) -> Result<i32
     call_with_result(store, instance, "i64f64_sqrt", vec![Value::I32(a)]).map_err(|_| Error::Sqrt)
 }
 
+pub fn i64f64_root(store: &mut Store, instance: &Instance, a: i32, n: u32) -> Result<i32, Error> {
+    call_with_result(
+        store,
+        instance,
+        "i64f64_root",
+        vec![Value::I32(a), Value::I32(n as i32)],
+    )
+    .map_err(|_| Error::Root)
+}
+
+pub fn i64f64_hypot(store: &mut Store, instance: &Instance, a: i32, b: i32) -> Result<i32, Error> {
+    call_with_result(
+        store,
+        instance,
+        "i64f64_hypot",
+        vec![Value::I32(a), Value::I32(b)],
+    )
+    .map_err(|_| Error::Hypot)
+}
+
+pub fn i64f64_to_number(store: &mut Store, instance: &Instance, a: i32) -> f64 {
+    let to_number: TypedFunction<i32, f64> = instance
+        .exports
+        .get_function("i64f64_toNumber")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    to_number.call(store, a).unwrap()
+}
+
+pub fn i64f64_from_number(
+    store: &mut Store,
+    instance: &Instance,
+    x: f64,
+    rounding: ckb_fixed::Rounding,
+) -> Result<i32, Error> {
+    call_with_result(
+        store,
+        instance,
+        "i64f64_fromNumber",
+        vec![Value::F64(x), Value::I32(rounding as i32)],
+    )
+    .map_err(|_| Error::FromNumber)
+}
+
+pub fn i64f64_int_part(store: &mut Store, instance: &Instance, a: i32) -> i64 {
+    let int_part: TypedFunction<i32, i64> = instance
+        .exports
+        .get_function("i64f64_intPart")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    int_part.call(store, a).unwrap()
+}
+
+pub fn i64f64_frac_part(store: &mut Store, instance: &Instance, a: i32) -> u64 {
+    let frac_part: TypedFunction<i32, i64> = instance
+        .exports
+        .get_function("i64f64_fracPart")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    frac_part.call(store, a).unwrap() as u64
+}
+
+pub fn i64f64_to_bigint(
+    store: &mut Store,
+    instance: &Instance,
+    a: i32,
+    rounding: ckb_fixed::Rounding,
+) -> Result<i64, Error> {
+    let memory = instance.exports.get_memory("memory").unwrap();
+    let add_to_stack_pointer: TypedFunction<i32, i32> = instance
+        .exports
+        .get_function("__wbindgen_add_to_stack_pointer")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    let ret_ptr = add_to_stack_pointer.call(store, -16).unwrap();
+
+    let to_bigint: TypedFunction<(i32, i32, i32), ()> = instance
+        .exports
+        .get_function("i64f64_toBigInt")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    to_bigint.call(store, ret_ptr, a, rounding as i32).unwrap();
+
+    // the `i64` is at offset 0 and the error flag at offset 12
+    let view = memory.view(store);
+    let mut value = [0u8; 8];
+    view.read(ret_ptr as u64, &mut value).unwrap();
+    let mut is_error = [0u8; 4];
+    view.read(ret_ptr as u64 + 12, &mut is_error).unwrap();
+    add_to_stack_pointer.call(store, 16).unwrap();
+
+    if i32::from_le_bytes(is_error) != 0 {
+        return Err(Error::ToBigInt);
+    }
+    Ok(i64::from_le_bytes(value))
+}
+
+pub fn i64f64_normal_cdf(store: &mut Store, instance: &Instance, a: i32) -> i32 {
+    let normal_cdf: TypedFunction<i32, i32> = instance
+        .exports
+        .get_function("i64f64_normalCdf")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    normal_cdf.call(store, a).unwrap()
+}
+
+pub fn i64f64_normal_inv_cdf(store: &mut Store, instance: &Instance, a: i32) -> Result<i32, Error> {
+    call_with_result(store, instance, "i64f64_normalInvCdf", vec![Value::I32(a)])
+        .map_err(|_| Error::NormalInvCdf)
+}
+
+pub fn sqrt_price_at_tick(store: &mut Store, instance: &Instance, tick: i32) -> Result<i32, Error> {
+    call_with_result(store, instance, "sqrtPriceAtTick", vec![Value::I32(tick)])
+        .map_err(|_| Error::SqrtPriceAtTick)
+}
+
+pub fn tick_at_sqrt_price(
+    store: &mut Store,
+    instance: &Instance,
+    sqrt_price: i32,
+) -> Result<i32, Error> {
+    call_with_result(
+        store,
+        instance,
+        "tickAtSqrtPrice",
+        vec![Value::I32(sqrt_price)],
+    )
+    .map_err(|_| Error::TickAtSqrtPrice)
+}
+
 pub fn to_le_bytes(store: &mut Store, instance: &Instance, ptr: i32) -> Vec<u8> {
+    call_returning_bytes(store, instance, "i64f64_toLeBytes", ptr)
+}
+
+pub fn to_be_bytes(store: &mut Store, instance: &Instance, ptr: i32) -> Vec<u8> {
+    call_returning_bytes(store, instance, "i64f64_toBeBytes", ptr)
+}
+
+pub fn to_hex(store: &mut Store, instance: &Instance, ptr: i32) -> String {
+    String::from_utf8(call_returning_bytes(store, instance, "i64f64_toHex", ptr)).unwrap()
+}
+
+pub fn to_json(store: &mut Store, instance: &Instance, ptr: i32) -> String {
+    String::from_utf8(call_returning_bytes(store, instance, "i64f64_toJSON", ptr)).unwrap()
+}
+
+/// Call a method that returns a byte array or a string, which is returned through the stack.
+fn call_returning_bytes(
+    store: &mut Store,
+    instance: &Instance,
+    fn_name: &'static str,
+    ptr: i32,
+) -> Vec<u8> {
     // Get memory export
     let memory = instance.exports.get_memory("memory").unwrap();
 
@@ -89,14 +311,13 @@ pub fn to_le_bytes(store: &mut Store, instance: &Instance, ptr: i32) -> Vec<u8>
     // Allocate 16 bytes for the return value
     let ret_ptr = add_to_stack_pointer.call(store, -16).unwrap();
 
-    // Call i64f64_toLeBytes
-    let to_le_bytes: TypedFunction<(i32, i32), ()> = instance
+    let func: TypedFunction<(i32, i32), ()> = instance
         .exports
-        .get_function("i64f64_toLeBytes")
+        .get_function(fn_name)
         .unwrap()
         .typed(store)
         .unwrap();
-    to_le_bytes.call(store, ret_ptr, ptr).unwrap();
+    func.call(store, ret_ptr, ptr).unwrap();
 
     // Read the results from memory
     let view = memory.view(store);
@@ -290,6 +511,128 @@ pub fn from_num(store: &mut Store, instance: &Instance, n: i64) -> Result<i32, E
         .map_err(|_| Error::FromNum)
 }
 
+/// The heap index standing in for the `Uint8Array` passed as `out` to batch functions.
+const OUT_ARRAY: i32 = 1000;
+
+/// Copy `bytes` into a fresh allocation, the way the bindings pass a `&[u8]`.
+fn pass_bytes(store: &mut Store, instance: &Instance, bytes: &[u8]) -> i32 {
+    let malloc: TypedFunction<(i32, i32), i32> = instance
+        .exports
+        .get_function("__wbindgen_malloc")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    let ptr = malloc.call(store, bytes.len() as i32, 1).unwrap();
+    let memory = instance.exports.get_memory("memory").unwrap();
+    memory.view(store).write(ptr as u64, bytes).unwrap();
+    ptr
+}
+
+/// Call a batch function with `args` followed by an `out` array of `len` bytes, returning
+/// the contents copied back to it.
+fn call_batch(
+    store: &mut Store,
+    instance: &Instance,
+    fn_name: &'static str,
+    args: Vec<Value>,
+    len: usize,
+) -> Result<Vec<u8>, Error> {
+    let memory = instance.exports.get_memory("memory").unwrap();
+    let add_to_stack_pointer: TypedFunction<i32, i32> = instance
+        .exports
+        .get_function("__wbindgen_add_to_stack_pointer")
+        .unwrap()
+        .typed(store)
+        .unwrap();
+    let ret_ptr = add_to_stack_pointer.call(store, -16).unwrap();
+    let out = pass_bytes(store, instance, &vec![0u8; len]);
+
+    let mut new_args = vec![Value::I32(ret_ptr)];
+    new_args.extend(args);
+    new_args.extend([
+        Value::I32(out),
+        Value::I32(len as i32),
+        Value::I32(OUT_ARRAY),
+    ]);
+    let func = instance.exports.get_function(fn_name).unwrap();
+    func.call(store, new_args.as_slice()).unwrap();
+
+    // `Result<(), _>` has the error at offset 0 and the error flag at offset 4
+    let mut is_error = [0u8; 4];
+    memory
+        .view(store)
+        .read(ret_ptr as u64 + 4, &mut is_error)
+        .unwrap();
+    add_to_stack_pointer.call(store, 16).unwrap();
+
+    let copied = TYPED_ARRAYS.with(|arrays| arrays.borrow_mut().remove(&OUT_ARRAY));
+    if i32::from_le_bytes(is_error) != 0 {
+        return Err(Error::Batch);
+    }
+    Ok(copied.unwrap())
+}
+
+pub fn add_many(
+    store: &mut Store,
+    instance: &Instance,
+    a: &[u8],
+    b: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let a_ptr = pass_bytes(store, instance, a);
+    let b_ptr = pass_bytes(store, instance, b);
+    let args = vec![
+        Value::I32(a_ptr),
+        Value::I32(a.len() as i32),
+        Value::I32(b_ptr),
+        Value::I32(b.len() as i32),
+    ];
+    call_batch(store, instance, "addMany", args, a.len())
+}
+
+/// Call one of the unary batch functions, such as `expMany`.
+pub fn map_many(
+    store: &mut Store,
+    instance: &Instance,
+    fn_name: &'static str,
+    values: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let ptr = pass_bytes(store, instance, values);
+    let args = vec![Value::I32(ptr), Value::I32(values.len() as i32)];
+    call_batch(store, instance, fn_name, args, values.len())
+}
+
+pub fn formula_new(
+    store: &mut Store,
+    instance: &Instance,
+    program: &[u8],
+    constants: &[u8],
+) -> Result<i32, Error> {
+    let program_ptr = pass_bytes(store, instance, program);
+    let constants_ptr = pass_bytes(store, instance, constants);
+    let args = vec![
+        Value::I32(program_ptr),
+        Value::I32(program.len() as i32),
+        Value::I32(constants_ptr),
+        Value::I32(constants.len() as i32),
+    ];
+    call_with_result(store, instance, "formula_new", args).map_err(|_| Error::Formula)
+}
+
+pub fn map_formula(
+    store: &mut Store,
+    instance: &Instance,
+    formula: i32,
+    values: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let ptr = pass_bytes(store, instance, values);
+    let args = vec![
+        Value::I32(formula),
+        Value::I32(ptr),
+        Value::I32(values.len() as i32),
+    ];
+    call_batch(store, instance, "mapFormula", args, values.len())
+}
+
 pub fn call_with_result(
     store: &mut Store,
     instance: &Instance,
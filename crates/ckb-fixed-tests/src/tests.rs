@@ -1,6 +1,9 @@
 use crate::{
-    from_num, from_str, i64f64_add, i64f64_exp, i64f64_ln, i64f64_pow, i64f64_sin,
-    initialize_wasmer, new, to_le_bytes,
+    add_many, formula_new, from_num, from_str, i64f64_add, i64f64_exp, i64f64_frac_part,
+    i64f64_from_number, i64f64_hypot, i64f64_int_part, i64f64_ln, i64f64_normal_cdf,
+    i64f64_normal_inv_cdf, i64f64_pow, i64f64_root, i64f64_sin, i64f64_sqrt, i64f64_to_bigint,
+    i64f64_to_number, initialize_wasmer, map_formula, map_many, new, sqrt_price_at_tick,
+    tick_at_sqrt_price, to_be_bytes, to_hex, to_json, to_le_bytes,
 };
 
 #[test]
@@ -61,6 +64,48 @@ fn test_from_str() {
     );
 }
 
+#[test]
+fn test_clmm_tick_math() {
+    use ckb_fixed::amm::clmm;
+
+    let (mut store, instance) = initialize_wasmer();
+    for tick in [clmm::MIN_TICK, -200000, -1, 0, 1, 77777, clmm::MAX_TICK] {
+        let wasm_value = sqrt_price_at_tick(&mut store, &instance, tick).unwrap();
+        let rust_value = clmm::sqrt_price_at_tick(tick).unwrap();
+        assert_eq!(
+            rust_value.to_le_bytes(),
+            to_le_bytes(&mut store, &instance, wasm_value)
+        );
+        assert_eq!(
+            tick_at_sqrt_price(&mut store, &instance, wasm_value).unwrap(),
+            clmm::tick_at_sqrt_price(&rust_value).unwrap()
+        );
+    }
+    assert!(sqrt_price_at_tick(&mut store, &instance, clmm::MAX_TICK + 1).is_err());
+}
+
+#[test]
+fn test_normal_distribution() {
+    let (mut store, instance) = initialize_wasmer();
+    for s in ["-7.5", "-1.2345", "0", "0.001", "1.96", "3"] {
+        let rust_value = ckb_fixed::I64F64::from_str(s).unwrap();
+        let value = from_str(&mut store, &instance, s).unwrap();
+        let cdf = i64f64_normal_cdf(&mut store, &instance, value);
+        let rust_cdf = rust_value.normal_cdf();
+        assert_eq!(
+            rust_cdf.to_le_bytes(),
+            to_le_bytes(&mut store, &instance, cdf)
+        );
+        let inv = i64f64_normal_inv_cdf(&mut store, &instance, cdf).unwrap();
+        assert_eq!(
+            rust_cdf.normal_inv_cdf().unwrap().to_le_bytes(),
+            to_le_bytes(&mut store, &instance, inv)
+        );
+    }
+    let value = from_num(&mut store, &instance, 1).unwrap();
+    assert!(i64f64_normal_inv_cdf(&mut store, &instance, value).is_err());
+}
+
 #[test]
 fn test_return_error() {
     let (mut store, instance) = initialize_wasmer();
@@ -141,3 +186,332 @@ fn test_fuzzing_4() {
     let result = i64f64_exp(&mut store, &instance, wasm_value);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_root() {
+    let (mut store, instance) = initialize_wasmer();
+    for (s, n) in [
+        ("27", 3),
+        ("-0.001", 3),
+        ("1e-15", 7),
+        ("12345.678", 2),
+        ("-32", 5),
+    ] {
+        let rust_value = ckb_fixed::I64F64::from_str(s).unwrap();
+        let value = from_str(&mut store, &instance, s).unwrap();
+        let root = i64f64_root(&mut store, &instance, value, n).unwrap();
+        assert_eq!(
+            rust_value.root(n).unwrap().to_le_bytes(),
+            to_le_bytes(&mut store, &instance, root)
+        );
+    }
+    let value = from_num(&mut store, &instance, -4).unwrap();
+    assert!(i64f64_root(&mut store, &instance, value, 2).is_err());
+}
+
+#[test]
+fn test_hypot() {
+    let (mut store, instance) = initialize_wasmer();
+    for (a, b) in [("3", "-4"), ("1e12", "2e12"), ("0.000001", "0")] {
+        let rust_value = ckb_fixed::I64F64::from_str(a)
+            .unwrap()
+            .hypot(&ckb_fixed::I64F64::from_str(b).unwrap())
+            .unwrap();
+        let a = from_str(&mut store, &instance, a).unwrap();
+        let b = from_str(&mut store, &instance, b).unwrap();
+        let result = i64f64_hypot(&mut store, &instance, a, b).unwrap();
+        assert_eq!(
+            rust_value.to_le_bytes(),
+            to_le_bytes(&mut store, &instance, result)
+        );
+    }
+}
+
+#[test]
+fn test_serde_matches_to_json() {
+    let (mut store, instance) = initialize_wasmer();
+    for s in [
+        "0",
+        "-1.5",
+        "0.003",
+        "123456789.000000001",
+        "-9223372036854775808",
+    ] {
+        let rust_value = ckb_fixed::I64F64::from_str(s).unwrap();
+        let value = from_str(&mut store, &instance, s).unwrap();
+        let json = serde_json::to_string(&rust_value).unwrap();
+        let wasm_json = to_json(&mut store, &instance, value);
+        assert_eq!(json, serde_json::to_string(&wasm_json).unwrap());
+    }
+}
+
+#[test]
+fn test_byte_encodings() {
+    use crate::ckb_fixed::{FixedError, I64F64};
+    let (mut store, instance) = initialize_wasmer();
+    for s in ["0", "1", "-1.5", "0.003", "-9223372036854775808"] {
+        let rust_value = I64F64::from_str(s).unwrap();
+        let value = from_str(&mut store, &instance, s).unwrap();
+
+        let mut be = rust_value.to_le_bytes();
+        be.reverse();
+        assert_eq!(rust_value.to_be_bytes(), be);
+        assert_eq!(to_be_bytes(&mut store, &instance, value), be);
+        assert_eq!(I64F64::from_be_bytes(&be).unwrap(), rust_value);
+
+        let hex = rust_value.to_hex();
+        assert_eq!(to_hex(&mut store, &instance, value), hex);
+        assert_eq!(I64F64::from_hex(&hex).unwrap(), rust_value);
+        assert_eq!(
+            I64F64::from_hex(&hex.to_uppercase().replace("0X", "0x")).unwrap(),
+            rust_value
+        );
+    }
+    assert_eq!(
+        I64F64::from_num(1).unwrap().to_hex(),
+        "0x00000000000000000100000000000000"
+    );
+
+    assert!(matches!(
+        I64F64::from_be_bytes(&[0; 15]),
+        Err(FixedError::InvalidLength)
+    ));
+    let hex = "00000000000000000100000000000000";
+    assert!(matches!(
+        I64F64::from_hex(hex),
+        Err(FixedError::InvalidNumber)
+    ));
+    assert!(matches!(
+        I64F64::from_hex(&format!("0X{hex}")),
+        Err(FixedError::InvalidNumber)
+    ));
+    assert!(matches!(
+        I64F64::from_hex(&format!("0x{hex}00")),
+        Err(FixedError::InvalidLength)
+    ));
+    assert!(matches!(
+        I64F64::from_hex(&format!("0x{}", &hex[1..])),
+        Err(FixedError::InvalidLength)
+    ));
+    assert!(matches!(
+        I64F64::from_hex(&format!("0x+{}", &hex[1..])),
+        Err(FixedError::InvalidNumber)
+    ));
+    assert!(matches!(
+        I64F64::from_hex(&format!("0x{}g", &hex[1..])),
+        Err(FixedError::InvalidNumber)
+    ));
+}
+
+#[test]
+fn test_le_array() {
+    use crate::ckb_fixed::{FixedError, I64F64};
+    let value = I64F64::from_str("-1.5").unwrap();
+    let array = value.to_le_array();
+    assert_eq!(array.to_vec(), value.to_le_bytes());
+    assert_eq!(I64F64::from_le_array(array), value);
+
+    let mut data = [0xffu8; 20];
+    value.write_le_bytes(&mut data[2..18]).unwrap();
+    assert_eq!(data[2..18], array);
+    assert_eq!(data[..2], [0xff, 0xff]);
+    assert_eq!(data[18..], [0xff, 0xff]);
+    assert!(matches!(
+        value.write_le_bytes(&mut data[..15]),
+        Err(FixedError::InvalidLength)
+    ));
+    assert!(matches!(
+        value.write_le_bytes(&mut data[..17]),
+        Err(FixedError::InvalidLength)
+    ));
+}
+
+#[test]
+fn test_f64_conversions() {
+    use crate::ckb_fixed::{types, FixedError, Rounding, I64F64};
+    let (mut store, instance) = initialize_wasmer();
+    for s in ["0", "1", "-1.5", "0.1", "1234.5678", "-9223372036854775808"] {
+        let rust_value = I64F64::from_str(s).unwrap();
+        let value = from_str(&mut store, &instance, s).unwrap();
+        assert_eq!(rust_value.to_f64(), s.parse::<f64>().unwrap());
+        assert_eq!(
+            i64f64_to_number(&mut store, &instance, value),
+            rust_value.to_f64()
+        );
+    }
+
+    let all = [
+        Rounding::Nearest,
+        Rounding::Floor,
+        Rounding::Ceil,
+        Rounding::TowardZero,
+    ];
+    // exactly representable values don't round
+    for x in [
+        0.0,
+        -0.0,
+        1.5,
+        -2.25,
+        1e18,
+        -9223372036854775808.0,
+        2f64.powi(-64),
+    ] {
+        for rounding in all {
+            let value = I64F64::from_f64_checked(x, rounding).unwrap();
+            assert_eq!(value.to_f64(), x);
+        }
+    }
+    // 2^-66 lies between 0 and DELTA, a quarter of the way
+    let delta = I64F64::from(types::I64F64::DELTA);
+    let neg_delta = I64F64::from(-types::I64F64::DELTA);
+    let zero = I64F64::from_num(0).unwrap();
+    let tiny = 2f64.powi(-66);
+    let expected = [
+        (Rounding::Nearest, &zero, &zero),
+        (Rounding::Floor, &zero, &neg_delta),
+        (Rounding::Ceil, &delta, &zero),
+        (Rounding::TowardZero, &zero, &zero),
+    ];
+    for (rounding, up, down) in expected {
+        assert_eq!(&I64F64::from_f64_checked(tiny, rounding).unwrap(), up);
+        assert_eq!(&I64F64::from_f64_checked(-tiny, rounding).unwrap(), down);
+    }
+    // ties go to even
+    let half_delta = 2f64.powi(-65);
+    assert_eq!(
+        I64F64::from_f64_checked(half_delta, Rounding::Nearest).unwrap(),
+        zero
+    );
+    assert_eq!(
+        I64F64::from_f64_checked(3.0 * half_delta, Rounding::Nearest).unwrap(),
+        I64F64::from(types::I64F64::DELTA * 2)
+    );
+
+    for x in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert!(matches!(
+            I64F64::from_f64_checked(x, Rounding::Nearest),
+            Err(FixedError::InvalidNumber)
+        ));
+    }
+    for x in [9223372036854775808.0, -9223372036854777856.0, 1e300] {
+        for rounding in all {
+            assert!(I64F64::from_f64_checked(x, rounding).is_err());
+        }
+    }
+
+    let value = i64f64_from_number(&mut store, &instance, 0.1, Rounding::Ceil).unwrap();
+    assert_eq!(
+        to_le_bytes(&mut store, &instance, value),
+        I64F64::from_f64_checked(0.1, Rounding::Ceil)
+            .unwrap()
+            .to_le_bytes()
+    );
+    assert!(i64f64_from_number(&mut store, &instance, f64::NAN, Rounding::Nearest).is_err());
+}
+
+#[test]
+fn test_bits_and_int_parts() {
+    use crate::ckb_fixed::{FixedError, Rounding, I64F64};
+    let (mut store, instance) = initialize_wasmer();
+    let value = I64F64::from_str("-1.25").unwrap();
+    assert_eq!(value.to_bits(), -(5i128 << 62));
+    assert_eq!(I64F64::from_bits(value.to_bits()), value);
+    assert_eq!(value.int_part(), -2);
+    assert_eq!(value.frac_part(), 3 << 62);
+    assert_eq!(
+        (value.int_part() as i128) << 64 | value.frac_part() as i128,
+        value.to_bits()
+    );
+
+    let expected = [
+        (Rounding::Nearest, -1),
+        (Rounding::Floor, -2),
+        (Rounding::Ceil, -1),
+        (Rounding::TowardZero, -1),
+    ];
+    let ptr = from_str(&mut store, &instance, "-1.25").unwrap();
+    for (rounding, n) in expected {
+        assert_eq!(value.to_int(rounding).unwrap(), n);
+        assert_eq!(
+            i64f64_to_bigint(&mut store, &instance, ptr, rounding).unwrap(),
+            n
+        );
+    }
+    assert_eq!(i64f64_int_part(&mut store, &instance, ptr), -2);
+    assert_eq!(i64f64_frac_part(&mut store, &instance, ptr), 3 << 62);
+
+    // ties go to even
+    let half = I64F64::from_str("2.5").unwrap();
+    assert_eq!(half.to_int(Rounding::Nearest).unwrap(), 2);
+    let max = I64F64::from_bits(i128::MAX);
+    assert_eq!(max.to_int(Rounding::Floor).unwrap(), i64::MAX);
+    assert!(max.to_int(Rounding::Ceil).is_err());
+    assert!(max.to_int(Rounding::Nearest).is_err());
+    let max_ptr = new(&mut store, &instance, &max.to_le_bytes()).unwrap();
+    assert!(i64f64_to_bigint(&mut store, &instance, max_ptr, Rounding::Ceil).is_err());
+
+    assert_eq!(I64F64::from_int(-7).unwrap(), I64F64::from_num(-7).unwrap());
+    assert_eq!(
+        I64F64::from_int(i64::MIN as i128).unwrap().to_bits(),
+        i128::MIN
+    );
+    for n in [i64::MAX as i128 + 1, i64::MIN as i128 - 1, i128::MAX] {
+        assert!(matches!(
+            I64F64::from_int(n),
+            Err(FixedError::InvalidNumber)
+        ));
+    }
+}
+
+#[test]
+fn test_batch_matches_scalar() {
+    let (mut store, instance) = initialize_wasmer();
+    let pack = |values: &[&str]| -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|s| ckb_fixed::I64F64::from_str(s).unwrap().to_le_bytes())
+            .collect()
+    };
+    let a = ["1.5", "1e-10", "12.345678", "0.5", "20"];
+    let b = ["3", "-0.75", "-1e-10", "0.001", "-7"];
+
+    let sums = add_many(&mut store, &instance, &pack(&a), &pack(&b)).unwrap();
+    for ((a, b), sum) in a.iter().zip(b).zip(sums.chunks_exact(16)) {
+        let a = from_str(&mut store, &instance, a).unwrap();
+        let b = from_str(&mut store, &instance, b).unwrap();
+        let expected = i64f64_add(&mut store, &instance, a, b).unwrap();
+        assert_eq!(to_le_bytes(&mut store, &instance, expected), sum);
+    }
+
+    type Scalar = fn(&mut wasmer::Store, &wasmer::Instance, i32) -> Result<i32, crate::Error>;
+    let unary: [(&'static str, Scalar); 3] = [
+        ("expMany", i64f64_exp),
+        ("lnMany", i64f64_ln),
+        ("sqrtMany", i64f64_sqrt),
+    ];
+    for (batch, scalar) in unary {
+        let results = map_many(&mut store, &instance, batch, &pack(&a)).unwrap();
+        for (a, result) in a.iter().zip(results.chunks_exact(16)) {
+            let a = from_str(&mut store, &instance, a).unwrap();
+            let expected = scalar(&mut store, &instance, a).unwrap();
+            assert_eq!(to_le_bytes(&mut store, &instance, expected), result);
+        }
+    }
+    // the batch stops at the first element that fails
+    assert!(map_many(&mut store, &instance, "lnMany", &pack(&b)).is_err());
+
+    // ln(x) + x^0.75
+    use ckb_fixed::batch::{ADD, CONST, LN, POW, X};
+    let program = [X, LN, X, CONST, 0, POW, ADD];
+    let formula = formula_new(&mut store, &instance, &program, &pack(&["0.75"])).unwrap();
+    let results = map_formula(&mut store, &instance, formula, &pack(&a)).unwrap();
+    for (a, result) in a.iter().zip(results.chunks_exact(16)) {
+        let x = from_str(&mut store, &instance, a).unwrap();
+        let ln = i64f64_ln(&mut store, &instance, x).unwrap();
+        let exponent = from_str(&mut store, &instance, "0.75").unwrap();
+        let pow = i64f64_pow(&mut store, &instance, x, exponent).unwrap();
+        let expected = i64f64_add(&mut store, &instance, ln, pow).unwrap();
+        assert_eq!(to_le_bytes(&mut store, &instance, expected), result);
+    }
+    assert!(formula_new(&mut store, &instance, &[X, ADD], &[]).is_err());
+}
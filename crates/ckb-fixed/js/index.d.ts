@@ -0,0 +1,124 @@
+import { I64F64, InitInput, Rounding, SyncInitInput } from "./pkg/ckb_fixed.js";
+
+export { I64F64, Rounding };
+
+/** Load the WASM module asynchronously, from `./pkg` by default. */
+export function init(moduleOrPath?: InitInput | Promise<InitInput>): Promise<void>;
+
+/** Load the WASM module from its bytes or a compiled `WebAssembly.Module`. */
+export function initSync(module: SyncInitInput): void;
+
+export type FixedErrorCode = "INVALID_LENGTH" | "INVALID_NUMBER" | "CALCULATION";
+
+/** The base class of every error thrown by the facade. */
+export class FixedError extends Error {
+  readonly code: FixedErrorCode;
+}
+
+/** A byte array of the wrong length. */
+export class InvalidLengthError extends FixedError {
+  readonly code: "INVALID_LENGTH";
+}
+
+/** A string, number or byte array that isn't a valid `I64F64`. */
+export class InvalidNumberError extends FixedError {
+  readonly code: "INVALID_NUMBER";
+}
+
+/** An overflow, a division by zero or an argument outside a function's domain. */
+export class CalculationError extends FixedError {
+  readonly code: "CALCULATION";
+}
+
+/**
+ * Anything that converts exactly to an `I64F64`: a decimal string, an integer `bigint`, a safe
+ * integer `number`, or an existing value.
+ */
+export type FixedLike = Fixed | I64F64 | string | bigint | number;
+
+/**
+ * An immutable `I64F64` with chainable arithmetic.
+ *
+ * The wrapped handle is registered with the bindings' `FinalizationRegistry`, so its WASM memory
+ * is released once the `Fixed` is garbage collected. Call `free()` to release it early.
+ */
+export class Fixed {
+  /** Copies `value`, so the caller keeps ownership of a `Fixed` or `I64F64` passed in. */
+  constructor(value: FixedLike);
+  /** The underlying handle, for the free functions of the bindings. */
+  readonly inner: I64F64;
+
+  /** Convert `value` to a `Fixed`, returning it unchanged if it already is one. */
+  static from(value: FixedLike): Fixed;
+  /** Create a `Fixed` from its raw bits, the value times `2^64`. */
+  static fromBits(bits: bigint): Fixed;
+  /** Create a `Fixed` from an approximate `number`, for display and analytics only. */
+  static fromNumber(x: number, rounding?: Rounding): Fixed;
+  static fromHex(s: string): Fixed;
+  static fromLeBytes(bytes: Uint8Array): Fixed;
+  static fromBeBytes(bytes: Uint8Array): Fixed;
+  static fromCanonicalDecimal(s: string, places: number): Fixed;
+
+  /** Release the WASM memory now instead of waiting for garbage collection. */
+  free(): void;
+
+  add(other: FixedLike): Fixed;
+  sub(other: FixedLike): Fixed;
+  mul(other: FixedLike): Fixed;
+  div(other: FixedLike): Fixed;
+  pow(other: FixedLike): Fixed;
+  hypot(other: FixedLike): Fixed;
+  logB(base: FixedLike): Fixed;
+  powi(n: number): Fixed;
+  root(n: number): Fixed;
+
+  floor(): Fixed;
+  ceil(): Fixed;
+  round(): Fixed;
+  exp(): Fixed;
+  ln(): Fixed;
+  sqrt(): Fixed;
+  cbrt(): Fixed;
+  log2(): Fixed;
+  log10(): Fixed;
+  exp2(): Fixed;
+  expm1(): Fixed;
+  ln1p(): Fixed;
+  sin(): Fixed;
+  cos(): Fixed;
+  tan(): Fixed;
+  erf(): Fixed;
+  erfc(): Fixed;
+  normalPdf(): Fixed;
+  normalCdf(): Fixed;
+  normalInvCdf(): Fixed;
+
+  eq(other: FixedLike): boolean;
+  lt(other: FixedLike): boolean;
+  gt(other: FixedLike): boolean;
+  le(other: FixedLike): boolean;
+  ge(other: FixedLike): boolean;
+  /** `-1`, `0` or `1` as `this` is less than, equal to or greater than `other`, for `sort`. */
+  cmp(other: FixedLike): -1 | 0 | 1;
+
+  /** Round to an integer, towards zero by default. */
+  toBigInt(rounding?: Rounding): bigint;
+  /** The raw bits, the value times `2^64`. */
+  toBits(): bigint;
+  /** The integer part, rounded towards −∞. */
+  intPart(): bigint;
+  /** The fractional part in units of `2^-64`, always non-negative. */
+  fracPart(): bigint;
+  /** The nearest `number`, for display and analytics only. */
+  toNumber(): number;
+  toHex(): string;
+  toLeBytes(): Uint8Array;
+  toBeBytes(): Uint8Array;
+  toCanonicalDecimal(places: number): string;
+  toString(): string;
+  /** The exact decimal string, so `JSON.stringify` never loses precision. */
+  toJSON(): string;
+  /** An approximate `number` for arithmetic hints, the exact string otherwise. */
+  [Symbol.toPrimitive](hint: "number"): number;
+  [Symbol.toPrimitive](hint: "string" | "default"): string;
+}
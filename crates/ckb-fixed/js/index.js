@@ -0,0 +1,310 @@
+// An idiomatic facade over the wasm-pack bindings in `./pkg`, see `index.d.ts` for the API.
+import initWasm, {
+  initSync as initWasmSync,
+  errorCode,
+  I64F64,
+  Rounding,
+} from "./pkg/ckb_fixed.js";
+
+export { I64F64, Rounding };
+
+/** Load the WASM module asynchronously, from `./pkg` by default. */
+export async function init(moduleOrPath) {
+  await initWasm(moduleOrPath === undefined ? undefined : { module_or_path: moduleOrPath });
+}
+
+/** Load the WASM module from its bytes or a compiled `WebAssembly.Module`. */
+export function initSync(module) {
+  initWasmSync({ module });
+}
+
+/** The base class of every error thrown by the facade. */
+export class FixedError extends Error {
+  constructor(message, code) {
+    super(message);
+    this.name = new.target.name;
+    this.code = code;
+  }
+}
+
+/** A byte array of the wrong length. */
+export class InvalidLengthError extends FixedError {
+  constructor(message = "Invalid length") {
+    super(message, "INVALID_LENGTH");
+  }
+}
+
+/** A string, number or byte array that isn't a valid `I64F64`. */
+export class InvalidNumberError extends FixedError {
+  constructor(message = "Invalid number") {
+    super(message, "INVALID_NUMBER");
+  }
+}
+
+/** An overflow, a division by zero or an argument outside a function's domain. */
+export class CalculationError extends FixedError {
+  constructor(message) {
+    super(message, "CALCULATION");
+  }
+}
+
+// the bindings throw the message of the Rust `FixedError` as a plain string
+function toFixedError(error) {
+  if (typeof error !== "string") {
+    return error;
+  }
+  switch (errorCode(error)) {
+    case "INVALID_LENGTH":
+      return new InvalidLengthError(error);
+    case "INVALID_NUMBER":
+      return new InvalidNumberError(error);
+    default:
+      return new CalculationError(error);
+  }
+}
+
+function call(f) {
+  try {
+    return f();
+  } catch (error) {
+    throw toFixedError(error);
+  }
+}
+
+function toI64F64(value) {
+  if (value instanceof Fixed) {
+    return value.inner;
+  }
+  if (value instanceof I64F64) {
+    return value;
+  }
+  switch (typeof value) {
+    case "string":
+      return call(() => I64F64.fromStr(value));
+    case "bigint":
+      return call(() => I64F64.fromBigInt(value));
+    case "number":
+      // only integers convert exactly, pass decimals as strings
+      if (!Number.isSafeInteger(value)) {
+        throw new InvalidNumberError(`${value} is not a safe integer`);
+      }
+      return call(() => I64F64.fromBigInt(BigInt(value)));
+    default:
+      throw new InvalidNumberError(`cannot convert ${typeof value} to Fixed`);
+  }
+}
+
+// a copy of a handle owned by someone else, so freeing either leaves the other valid
+function cloneI64F64(inner) {
+  return I64F64.fromLeBytes(inner.toLeBytes());
+}
+
+// wrap a handle just created for the facade, without the copy the constructor makes
+function adopt(inner) {
+  const value = Object.create(Fixed.prototype);
+  value.inner = inner;
+  return value;
+}
+
+// run `f` on the `I64F64` of `value`, freeing it afterwards if it was converted just for `f`
+function withI64F64(value, f) {
+  const inner = toI64F64(value);
+  try {
+    return call(() => f(inner));
+  } finally {
+    if (inner !== value && !(value instanceof Fixed)) {
+      inner.free();
+    }
+  }
+}
+
+/**
+ * An immutable `I64F64` with chainable arithmetic.
+ *
+ * The wrapped handle is registered with the bindings' `FinalizationRegistry`, so its WASM memory
+ * is released once the `Fixed` is garbage collected. Call `free()` to release it early.
+ */
+export class Fixed {
+  /** Copies `value`, so the caller keeps ownership of a `Fixed` or `I64F64` passed in. */
+  constructor(value) {
+    const inner = toI64F64(value);
+    this.inner = value instanceof Fixed || value instanceof I64F64 ? cloneI64F64(inner) : inner;
+  }
+
+  /** Convert `value` to a `Fixed`, returning it unchanged if it already is one. */
+  static from(value) {
+    return value instanceof Fixed ? value : new Fixed(value);
+  }
+
+  static fromBits(bits) {
+    return adopt(call(() => I64F64.fromBits(bits)));
+  }
+
+  static fromNumber(x, rounding = Rounding.Nearest) {
+    return adopt(call(() => I64F64.fromNumber(x, rounding)));
+  }
+
+  static fromHex(s) {
+    return adopt(call(() => I64F64.fromHex(s)));
+  }
+
+  static fromLeBytes(bytes) {
+    return adopt(call(() => I64F64.fromLeBytes(bytes)));
+  }
+
+  static fromBeBytes(bytes) {
+    return adopt(call(() => I64F64.fromBeBytes(bytes)));
+  }
+
+  static fromCanonicalDecimal(s, places) {
+    return adopt(call(() => I64F64.fromCanonicalDecimal(s, places)));
+  }
+
+  /** Release the WASM memory now instead of waiting for garbage collection. */
+  free() {
+    this.inner.free();
+  }
+
+  add(other) {
+    return withI64F64(other, (b) => adopt(this.inner.add(b)));
+  }
+
+  sub(other) {
+    return withI64F64(other, (b) => adopt(this.inner.sub(b)));
+  }
+
+  mul(other) {
+    return withI64F64(other, (b) => adopt(this.inner.mul(b)));
+  }
+
+  div(other) {
+    return withI64F64(other, (b) => adopt(this.inner.div(b)));
+  }
+
+  pow(other) {
+    return withI64F64(other, (b) => adopt(this.inner.pow(b)));
+  }
+
+  hypot(other) {
+    return withI64F64(other, (b) => adopt(this.inner.hypot(b)));
+  }
+
+  logB(base) {
+    return withI64F64(base, (b) => adopt(this.inner.logB(b)));
+  }
+
+  powi(n) {
+    return adopt(call(() => this.inner.powi(n)));
+  }
+
+  root(n) {
+    return adopt(call(() => this.inner.root(n)));
+  }
+
+  eq(other) {
+    return withI64F64(other, (b) => this.inner.eq(b));
+  }
+
+  lt(other) {
+    return withI64F64(other, (b) => this.inner.lt(b));
+  }
+
+  gt(other) {
+    return withI64F64(other, (b) => this.inner.gt(b));
+  }
+
+  le(other) {
+    return withI64F64(other, (b) => this.inner.le(b));
+  }
+
+  ge(other) {
+    return withI64F64(other, (b) => this.inner.ge(b));
+  }
+
+  /** `-1`, `0` or `1` as `this` is less than, equal to or greater than `other`, for `sort`. */
+  cmp(other) {
+    return withI64F64(other, (b) => (this.inner.lt(b) ? -1 : this.inner.gt(b) ? 1 : 0));
+  }
+
+  toBigInt(rounding = Rounding.TowardZero) {
+    return call(() => this.inner.toBigInt(rounding));
+  }
+
+  toBits() {
+    return this.inner.toBits();
+  }
+
+  intPart() {
+    return this.inner.intPart();
+  }
+
+  fracPart() {
+    return this.inner.fracPart();
+  }
+
+  toNumber() {
+    return this.inner.toNumber();
+  }
+
+  toHex() {
+    return this.inner.toHex();
+  }
+
+  toLeBytes() {
+    return this.inner.toLeBytes();
+  }
+
+  toBeBytes() {
+    return this.inner.toBeBytes();
+  }
+
+  toCanonicalDecimal(places) {
+    return call(() => this.inner.toCanonicalDecimal(places));
+  }
+
+  toString() {
+    return this.inner.toString();
+  }
+
+  /** The exact decimal string, so `JSON.stringify` never loses precision. */
+  toJSON() {
+    return this.inner.toJSON();
+  }
+
+  /** An approximate `number` for arithmetic hints, the exact string otherwise. */
+  [Symbol.toPrimitive](hint) {
+    return hint === "number" ? this.toNumber() : this.toString();
+  }
+}
+
+// the unary functions all take no argument and return a new `I64F64`
+for (const name of [
+  "floor",
+  "ceil",
+  "round",
+  "exp",
+  "ln",
+  "sqrt",
+  "cbrt",
+  "log2",
+  "log10",
+  "exp2",
+  "expm1",
+  "ln1p",
+  "sin",
+  "cos",
+  "tan",
+  "erf",
+  "erfc",
+  "normalPdf",
+  "normalCdf",
+  "normalInvCdf",
+]) {
+  Object.defineProperty(Fixed.prototype, name, {
+    value: function () {
+      return adopt(call(() => this.inner[name]()));
+    },
+    writable: true,
+    configurable: true,
+  });
+}
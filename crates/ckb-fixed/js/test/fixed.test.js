@@ -0,0 +1,101 @@
+import assert from "node:assert/strict";
+import { readFileSync } from "node:fs";
+import { test } from "node:test";
+import {
+  CalculationError,
+  Fixed,
+  FixedError,
+  I64F64,
+  InvalidLengthError,
+  InvalidNumberError,
+  Rounding,
+  initSync,
+} from "../index.js";
+
+initSync(readFileSync(new URL("../pkg/ckb_fixed_bg.wasm", import.meta.url)));
+
+test("chains arithmetic over mixed operands", () => {
+  const value = Fixed.from("1.5").add(2).mul(3n).sub(new Fixed("0.5")).div("2");
+  assert.equal(value.toString(), "5");
+  assert.ok(value.eq(5));
+  assert.ok(Fixed.from(2).sqrt().mul(Fixed.from(2).sqrt()).sub(2).lt("0.000001"));
+  assert.equal(Fixed.from(8).cbrt().toString(), "2");
+  assert.deepEqual(
+    ["3", "-1", "2.5"].map((s) => Fixed.from(s)).sort((a, b) => a.cmp(b)).map(String),
+    ["-1", "2.5", "3"],
+  );
+});
+
+test("matches the bindings bit for bit", () => {
+  const a = I64F64.fromStr("1.25");
+  const b = I64F64.fromStr("0.3");
+  const expected = a.exp().mul(b.ln());
+  const actual = Fixed.from("1.25").exp().mul(Fixed.from("0.3").ln());
+  assert.equal(actual.toBits(), expected.toBits());
+  assert.equal(actual.toHex(), expected.toHex());
+});
+
+test("converts to primitives and JSON", () => {
+  const value = Fixed.from("-1.25");
+  assert.equal(`${value}`, "-1.25");
+  assert.equal(+value, -1.25);
+  assert.equal(value * 2, -2.5);
+  assert.equal(JSON.stringify({ value }), '{"value":"-1.25"}');
+  assert.equal(value.toBits(), -(5n << 62n));
+  assert.equal(Fixed.fromBits(value.toBits()).toString(), "-1.25");
+  assert.equal(value.intPart(), -2n);
+  assert.equal(value.fracPart(), 3n << 62n);
+  assert.equal(value.toBigInt(), -1n);
+  assert.equal(value.toBigInt(Rounding.Floor), -2n);
+  assert.equal(Fixed.from(2n ** 62n).toBigInt(), 2n ** 62n);
+  assert.equal(Fixed.fromHex(value.toHex()).toString(), "-1.25");
+  assert.equal(Fixed.fromLeBytes(value.toLeBytes()).toString(), "-1.25");
+  assert.equal(value.toCanonicalDecimal(3), "-1.250");
+});
+
+test("throws FixedError subclasses with codes", () => {
+  const cases = [
+    [() => Fixed.from("abc"), InvalidNumberError, "INVALID_NUMBER"],
+    [() => Fixed.from(0.1), InvalidNumberError, "INVALID_NUMBER"],
+    [() => Fixed.from(2n ** 63n), InvalidNumberError, "INVALID_NUMBER"],
+    [() => Fixed.fromLeBytes(new Uint8Array(15)), InvalidLengthError, "INVALID_LENGTH"],
+    [() => Fixed.from(1).div(0), CalculationError, "CALCULATION"],
+    [() => Fixed.from(-1).ln(), CalculationError, "CALCULATION"],
+    [() => Fixed.fromBits(2n ** 127n - 1n).toBigInt(Rounding.Ceil), CalculationError, "CALCULATION"],
+  ];
+  for (const [f, type, code] of cases) {
+    assert.throws(f, (error) => {
+      assert.ok(error instanceof type);
+      assert.ok(error instanceof FixedError);
+      assert.ok(error instanceof Error);
+      assert.equal(error.code, code);
+      assert.equal(error.name, type.name);
+      return true;
+    });
+  }
+});
+
+test("leaves the messages of the bindings unchanged", () => {
+  assert.throws(() => I64F64.fromLeBytes(new Uint8Array(15)), (error) => error === "Invalid length");
+  assert.throws(() => I64F64.fromStr("abc"), (error) => error === "Invalid number");
+  assert.throws(
+    () => Fixed.fromLeBytes(new Uint8Array(15)),
+    (error) => error.message === "Invalid length",
+  );
+});
+
+test("frees values early", () => {
+  const value = Fixed.from(1);
+  value.free();
+  assert.throws(() => value.add(1));
+});
+
+test("copies the handles it is constructed from", () => {
+  const inner = I64F64.fromStr("1.5");
+  const value = new Fixed(inner);
+  inner.free();
+  assert.equal(value.toString(), "1.5");
+  const copy = new Fixed(value);
+  value.free();
+  assert.equal(copy.add(1).toString(), "2.5");
+});
@@ -0,0 +1,343 @@
+//! Concentrated-liquidity tick math, in the style of Uniswap v3.
+//!
+//! A tick `i` corresponds to the price `1.0001^i`, so its square root is
+//! `1.0001^(i/2)`. Square root prices are evaluated from a precomputed table of
+//! `sqrt(1.0001)^(2^k)` rather than through `pow`, which keeps the relative error
+//! below 2^-59 and the result identical on every target.
+use crate::rounding::{div_up, mul_up};
+use crate::{transcendental, types, FixedError, I64F64};
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+/// The minimum tick, whose square root price is about `2^-32`.
+pub const MIN_TICK: i32 = -MAX_TICK;
+/// The maximum tick, whose square root price is about `2^32`.
+pub const MAX_TICK: i32 = 443636;
+
+// generate with
+// ```python
+// from decimal import *
+// getcontext().prec = 120
+// for i in range(19):
+//     v = (Decimal("1.0001") ** (2 ** i)).sqrt() * 2 ** 64
+//     print("0x%032X" % int(v.to_integral_value(ROUND_HALF_EVEN)))
+// ```
+/// sqrt(1.0001)^(2^i) lookup table
+const SQRT_1_0001_POW: [types::I64F64; 19] = [
+    types::I64F64::from_bits(0x0000000000000001000346D6FF11672B),
+    types::I64F64::from_bits(0x000000000000000100068DB8BAC710CB),
+    types::I64F64::from_bits(0x0000000000000001000D1B9C68ABE5F7),
+    types::I64F64::from_bits(0x0000000000000001001A37E4A234CB08),
+    types::I64F64::from_bits(0x000000000000000100347278AB0E92AE),
+    types::I64F64::from_bits(0x00000000000000010068EFB00A525481),
+    types::I64F64::from_bits(0x000000000000000100D20A63B417383A),
+    types::I64F64::from_bits(0x000000000000000101A4C11C742DD773),
+    types::I64F64::from_bits(0x0000000000000001034C35C31F64CFA7),
+    types::I64F64::from_bits(0x000000000000000106A34B78C8AAFFC0),
+    types::I64F64::from_bits(0x00000000000000010D72A6A46CCD8BCF),
+    types::I64F64::from_bits(0x00000000000000011B9A258E63928597),
+    types::I64F64::from_bits(0x00000000000000013A2E2BDA04F8379F),
+    types::I64F64::from_bits(0x000000000000000181954BE69E0DA8FE),
+    types::I64F64::from_bits(0x000000000000000244C2655D185A0291),
+    types::I64F64::from_bits(0x000000000000000525816EEB9F935B1C),
+    types::I64F64::from_bits(0x000000000000001A7C8D00B551684FF5),
+    types::I64F64::from_bits(0x00000000000002BD893D0B2DF7C97884),
+    types::I64F64::from_bits(0x0000000000078278E1E19E448CF8B95D),
+];
+
+/// square root price at `MIN_TICK`
+const MIN_SQRT_PRICE: types::I64F64 = types::I64F64::from_bits(0x100013B50);
+/// square root price at `MAX_TICK`
+const MAX_SQRT_PRICE: types::I64F64 = types::I64F64::from_bits(0xFFFEC4B135BB7F31C7A02E8E);
+
+/// log2(sqrt(1.0001))
+const LOG2_SQRT_1_0001: types::I64F64 = types::I64F64::from_bits(0x4BA28E9410863);
+
+fn sqrt_price_at_tick_inner(tick: i32) -> Result<types::I64F64, FixedError> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(FixedError::Calculation("tick out of range"));
+    }
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = types::I64F64::ONE;
+    for (i, factor) in SQRT_1_0001_POW.iter().enumerate() {
+        if abs_tick & (1 << i) != 0 {
+            ratio = ratio
+                .checked_mul(*factor)
+                .ok_or(FixedError::Calculation("sqrt price overflow"))?;
+        }
+    }
+    if tick < 0 {
+        ratio = types::I64F64::ONE
+            .checked_div(ratio)
+            .ok_or(FixedError::Calculation("sqrt price overflow"))?;
+    }
+    Ok(ratio)
+}
+
+fn sorted_sqrt_prices(
+    a: &I64F64,
+    b: &I64F64,
+) -> Result<(types::I64F64, types::I64F64), FixedError> {
+    let (a, b) = if a.inner <= b.inner {
+        (a.inner, b.inner)
+    } else {
+        (b.inner, a.inner)
+    };
+    if a <= types::I64F64::ZERO {
+        return Err(FixedError::Calculation("sqrt price must be positive"));
+    }
+    Ok((a, b))
+}
+
+/// Calculate the square root price `1.0001^(tick/2)` of a tick.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = sqrtPriceAtTick))]
+pub fn sqrt_price_at_tick(tick: i32) -> Result<I64F64, FixedError> {
+    let inner = sqrt_price_at_tick_inner(tick)?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the greatest tick whose square root price is less than or equal to `sqrt_price`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = tickAtSqrtPrice))]
+pub fn tick_at_sqrt_price(sqrt_price: &I64F64) -> Result<i32, FixedError> {
+    let sqrt_price = sqrt_price.inner;
+    if !(MIN_SQRT_PRICE..=MAX_SQRT_PRICE).contains(&sqrt_price) {
+        return Err(FixedError::Calculation("sqrt price out of range"));
+    }
+    let log2: types::I64F64 = transcendental::log2(sqrt_price)
+        .map_err(|_| FixedError::Calculation("log2 calculation failed"))?;
+    let estimate = (log2 / LOG2_SQRT_1_0001).floor().to_num::<i32>();
+    // The estimate is off by at most one tick, settle it against the table.
+    let mut tick = estimate.clamp(MIN_TICK, MAX_TICK);
+    while tick > MIN_TICK && sqrt_price_at_tick_inner(tick)? > sqrt_price {
+        tick -= 1;
+    }
+    while tick < MAX_TICK && sqrt_price_at_tick_inner(tick + 1)? <= sqrt_price {
+        tick += 1;
+    }
+    Ok(tick)
+}
+
+/// Calculate the amount of token0 between two square root prices,
+/// `liquidity * (sqrt_price_b - sqrt_price_a) / (sqrt_price_a * sqrt_price_b)`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = amount0Delta))]
+pub fn amount0_delta(
+    sqrt_price_a: &I64F64,
+    sqrt_price_b: &I64F64,
+    liquidity: &I64F64,
+    round_up: bool,
+) -> Result<I64F64, FixedError> {
+    let (a, b) = sorted_sqrt_prices(sqrt_price_a, sqrt_price_b)?;
+    let liquidity = liquidity.inner;
+    if liquidity < types::I64F64::ZERO {
+        return Err(FixedError::Calculation("liquidity must not be negative"));
+    }
+    let diff = b - a;
+    let inner = if round_up {
+        div_up(diff, b)
+            .and_then(|r| mul_up(r, liquidity))
+            .and_then(|r| div_up(r, a))
+    } else {
+        diff.checked_div(b)
+            .and_then(|r| r.checked_mul(liquidity))
+            .and_then(|r| r.checked_div(a))
+    }
+    .ok_or(FixedError::Calculation("amount0 overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the amount of token1 between two square root prices,
+/// `liquidity * (sqrt_price_b - sqrt_price_a)`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = amount1Delta))]
+pub fn amount1_delta(
+    sqrt_price_a: &I64F64,
+    sqrt_price_b: &I64F64,
+    liquidity: &I64F64,
+    round_up: bool,
+) -> Result<I64F64, FixedError> {
+    let (a, b) = sorted_sqrt_prices(sqrt_price_a, sqrt_price_b)?;
+    let liquidity = liquidity.inner;
+    if liquidity < types::I64F64::ZERO {
+        return Err(FixedError::Calculation("liquidity must not be negative"));
+    }
+    let diff = b - a;
+    let inner = if round_up {
+        mul_up(liquidity, diff)
+    } else {
+        liquidity.checked_mul(diff)
+    }
+    .ok_or(FixedError::Calculation("amount1 overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the liquidity provided by `amount0` between two square root prices, rounded down.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = liquidityForAmount0))]
+pub fn liquidity_for_amount0(
+    sqrt_price_a: &I64F64,
+    sqrt_price_b: &I64F64,
+    amount0: &I64F64,
+) -> Result<I64F64, FixedError> {
+    let (a, b) = sorted_sqrt_prices(sqrt_price_a, sqrt_price_b)?;
+    let amount0 = amount0.inner;
+    if amount0 < types::I64F64::ZERO {
+        return Err(FixedError::Calculation("amount0 must not be negative"));
+    }
+    // a * b alone reaches 2^63 near the top of the tick range; amount0 * a is
+    // at most the liquidity, so dividing by b - a before multiplying by b
+    // overflows only when the result does
+    let inner = match a.checked_mul(b).and_then(|r| r.checked_mul(amount0)) {
+        Some(product) => product.checked_div(b - a),
+        None => amount0
+            .checked_mul(a)
+            .and_then(|r| r.checked_div(b - a))
+            .and_then(|r| r.checked_mul(b)),
+    }
+    .ok_or(FixedError::Calculation("liquidity overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the liquidity provided by `amount1` between two square root prices, rounded down.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = liquidityForAmount1))]
+pub fn liquidity_for_amount1(
+    sqrt_price_a: &I64F64,
+    sqrt_price_b: &I64F64,
+    amount1: &I64F64,
+) -> Result<I64F64, FixedError> {
+    let (a, b) = sorted_sqrt_prices(sqrt_price_a, sqrt_price_b)?;
+    if amount1.inner < types::I64F64::ZERO {
+        return Err(FixedError::Calculation("amount1 must not be negative"));
+    }
+    let inner = amount1
+        .inner
+        .checked_div(b - a)
+        .ok_or(FixedError::Calculation("liquidity overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+    use approx::assert_relative_eq;
+    use fixed::traits::LossyInto;
+
+    #[test]
+    fn sqrt_price_at_tick_works() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), I64F64::from_num(1).unwrap());
+        for tick in [
+            1, -1, 10, -10, 1000, -1000, 100000, -100000, MAX_TICK, MIN_TICK,
+        ] {
+            let result: f64 = sqrt_price_at_tick(tick).unwrap().inner.lossy_into();
+            let expected = 1.0001_f64.powf(tick as f64 / 2.0);
+            assert_relative_eq!(result, expected, max_relative = 1.0e-9);
+        }
+        assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap().inner, MIN_SQRT_PRICE);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK).unwrap().inner, MAX_SQRT_PRICE);
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_works() {
+        assert_eq!(
+            tick_at_sqrt_price(&I64F64::from_num(1).unwrap()).unwrap(),
+            0
+        );
+        assert_eq!(tick_at_sqrt_price(&num("2")).unwrap(), 13863);
+        assert_eq!(tick_at_sqrt_price(&num("0.5")).unwrap(), -13864);
+
+        let min = sqrt_price_at_tick(MIN_TICK).unwrap();
+        let max = sqrt_price_at_tick(MAX_TICK).unwrap();
+        let delta = I64F64::from(types::I64F64::DELTA);
+        assert!(tick_at_sqrt_price(&min.sub(&delta).unwrap()).is_err());
+        assert!(tick_at_sqrt_price(&max.add(&delta).unwrap()).is_err());
+        assert!(tick_at_sqrt_price(&I64F64::from_num(0).unwrap()).is_err());
+    }
+
+    #[test]
+    fn tick_round_trip_exhaustive() {
+        let delta = types::I64F64::DELTA;
+        let mut prev = types::I64F64::ZERO;
+        for tick in MIN_TICK..=MAX_TICK {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert!(sqrt_price.inner > prev, "not monotonic at tick {}", tick);
+            prev = sqrt_price.inner;
+            assert_eq!(tick_at_sqrt_price(&sqrt_price).unwrap(), tick);
+            if tick > MIN_TICK {
+                let below = I64F64::from(sqrt_price.inner - delta);
+                assert_eq!(tick_at_sqrt_price(&below).unwrap(), tick - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn amount_delta_works() {
+        let one = num("1");
+        let two = num("2");
+        let three = num("3");
+        let liquidity = num("1000");
+
+        assert_eq!(
+            amount0_delta(&one, &two, &liquidity, false).unwrap(),
+            num("500")
+        );
+        assert_eq!(
+            amount0_delta(&two, &one, &liquidity, true).unwrap(),
+            num("500")
+        );
+        assert_eq!(
+            amount1_delta(&one, &two, &liquidity, false).unwrap(),
+            liquidity
+        );
+
+        // 1000 * (3 - 2) / 6 is not representable exactly
+        let down = amount0_delta(&two, &three, &liquidity, false).unwrap();
+        let up = amount0_delta(&two, &three, &liquidity, true).unwrap();
+        assert!(down.lt(&up));
+        let result: f64 = down.inner.lossy_into();
+        assert_relative_eq!(result, 1000.0 / 6.0, max_relative = 1.0e-15);
+
+        assert!(amount0_delta(&num("0"), &two, &liquidity, false).is_err());
+        assert!(amount1_delta(&one, &two, &num("-1"), false).is_err());
+    }
+
+    #[test]
+    fn liquidity_for_amount_works() {
+        let a = sqrt_price_at_tick(-1000).unwrap();
+        let b = sqrt_price_at_tick(2000).unwrap();
+        let amount = num("12345.6789");
+
+        let liquidity = liquidity_for_amount0(&a, &b, &amount).unwrap();
+        let back = amount0_delta(&a, &b, &liquidity, false).unwrap();
+        assert!(back.le(&amount));
+        let result: f64 = back.inner.lossy_into();
+        assert_relative_eq!(result, 12345.6789, max_relative = 1.0e-12);
+
+        let liquidity = liquidity_for_amount1(&a, &b, &amount).unwrap();
+        let back = amount1_delta(&a, &b, &liquidity, false).unwrap();
+        assert!(back.le(&amount));
+        let result: f64 = back.inner.lossy_into();
+        assert_relative_eq!(result, 12345.6789, max_relative = 1.0e-12);
+
+        assert!(liquidity_for_amount1(&a, &a, &amount).is_err());
+        assert!(liquidity_for_amount0(&a, &b, &num("-1")).is_err());
+        assert!(liquidity_for_amount1(&a, &b, &num("-1")).is_err());
+    }
+
+    #[test]
+    fn liquidity_near_max_tick() {
+        // a * b is about 2.1e18 here, above the integer range once times the amount
+        let a = sqrt_price_at_tick(400000).unwrap();
+        let b = sqrt_price_at_tick(MAX_TICK).unwrap();
+        let amount = num("10");
+        let liquidity = liquidity_for_amount0(&a, &b, &amount).unwrap();
+        let (fa, fb): (f64, f64) = (a.inner.lossy_into(), b.inner.lossy_into());
+        let result: f64 = liquidity.inner.lossy_into();
+        assert_relative_eq!(result, 10.0 * fa * fb / (fb - fa), max_relative = 1.0e-12);
+        let back = amount0_delta(&a, &b, &liquidity, false).unwrap();
+        assert!(back.le(&amount));
+
+        let liquidity = liquidity_for_amount1(&a, &b, &amount).unwrap();
+        let back = amount1_delta(&a, &b, &liquidity, false).unwrap();
+        assert!(back.le(&amount));
+    }
+}
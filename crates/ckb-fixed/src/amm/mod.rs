@@ -0,0 +1,5 @@
+//! Automated market maker math.
+//!
+//! Every function here is built on the `I64F64` wrapper so that the on-chain
+//! script and the WASM bindings produce bit-identical results.
+pub mod clmm;
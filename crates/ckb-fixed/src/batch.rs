@@ -0,0 +1,360 @@
+//! Element-wise operations over packed arrays.
+//!
+//! Crossing between JavaScript and WASM once per number dominates the cost of
+//! pricing many quotes, so these functions take whole arrays of values packed as
+//! consecutive 16-byte little-endian numbers and write the results into an output
+//! array of the same length. Every element goes through the same code as the
+//! scalar API, so the results are bit-identical to calling it in a loop.
+//!
+//! The first element that fails stops the batch with its error, and the elements
+//! before it have already been written to the output.
+//!
+//! A [`Formula`] maps each value through a short stack program. Each opcode is one
+//! byte, and `CONST` is followed by a one-byte index into the constants:
+//!
+//! | opcode | name    | effect                               |
+//! |--------|---------|--------------------------------------|
+//! | 0      | `X`     | push the element                     |
+//! | 1      | `CONST` | push a constant                      |
+//! | 2      | `ADD`   | pop `b`, `a` and push `a + b`        |
+//! | 3      | `SUB`   | pop `b`, `a` and push `a - b`        |
+//! | 4      | `MUL`   | pop `b`, `a` and push `a * b`        |
+//! | 5      | `DIV`   | pop `b`, `a` and push `a / b`        |
+//! | 6      | `POW`   | pop `b`, `a` and push `a^b`          |
+//! | 7      | `EXP`   | replace the top with its exponential |
+//! | 8      | `LN`    | replace the top with its logarithm   |
+//! | 9      | `SQRT`  | replace the top with its square root |
+//!
+//! For example `x * 1.05 + 2` is `X CONST 0 MUL CONST 1 ADD` with the constants
+//! `1.05` and `2`.
+#[cfg(feature = "alloc")]
+use crate::packed::unpack;
+use crate::{FixedError, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+pub const X: u8 = 0;
+pub const CONST: u8 = 1;
+pub const ADD: u8 = 2;
+pub const SUB: u8 = 3;
+pub const MUL: u8 = 4;
+pub const DIV: u8 = 5;
+pub const POW: u8 = 6;
+pub const EXP: u8 = 7;
+pub const LN: u8 = 8;
+pub const SQRT: u8 = 9;
+
+/// The deepest stack a [`Formula`] may use.
+pub const MAX_STACK_DEPTH: usize = 16;
+
+fn read(chunk: &[u8]) -> Result<I64F64, FixedError> {
+    I64F64::from_le_bytes(chunk)
+}
+
+/// Apply `f` to each packed value of `values`, writing the results to `out`.
+fn map<F>(values: &[u8], out: &mut [u8], mut f: F) -> Result<(), FixedError>
+where
+    F: FnMut(&I64F64) -> Result<I64F64, FixedError>,
+{
+    if values.len() % 16 != 0 || out.len() != values.len() {
+        return Err(FixedError::InvalidLength);
+    }
+    for (value, out) in values.chunks_exact(16).zip(out.chunks_exact_mut(16)) {
+        f(&read(value)?)?.write_le_bytes(out)?;
+    }
+    Ok(())
+}
+
+/// Apply `f` to each pair of packed values of `a` and `b`, writing the results to `out`.
+fn zip<F>(a: &[u8], b: &[u8], out: &mut [u8], f: F) -> Result<(), FixedError>
+where
+    F: Fn(&I64F64, &I64F64) -> Result<I64F64, FixedError>,
+{
+    if a.len() % 16 != 0 || b.len() != a.len() || out.len() != a.len() {
+        return Err(FixedError::InvalidLength);
+    }
+    let pairs = a.chunks_exact(16).zip(b.chunks_exact(16));
+    for ((a, b), out) in pairs.zip(out.chunks_exact_mut(16)) {
+        f(&read(a)?, &read(b)?)?.write_le_bytes(out)?;
+    }
+    Ok(())
+}
+
+/// Add two packed arrays element by element.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = addMany))]
+pub fn add_many(a: &[u8], b: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    zip(a, b, out, I64F64::add)
+}
+
+/// Subtract two packed arrays element by element.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = subMany))]
+pub fn sub_many(a: &[u8], b: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    zip(a, b, out, I64F64::sub)
+}
+
+/// Multiply two packed arrays element by element.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = mulMany))]
+pub fn mul_many(a: &[u8], b: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    zip(a, b, out, I64F64::mul)
+}
+
+/// Divide two packed arrays element by element.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = divMany))]
+pub fn div_many(a: &[u8], b: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    zip(a, b, out, I64F64::div)
+}
+
+/// Add `scalar` to each packed value.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = addScalar))]
+pub fn add_scalar(values: &[u8], scalar: &I64F64, out: &mut [u8]) -> Result<(), FixedError> {
+    map(values, out, |v| v.add(scalar))
+}
+
+/// Multiply each packed value by `scalar`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = mulScalar))]
+pub fn mul_scalar(values: &[u8], scalar: &I64F64, out: &mut [u8]) -> Result<(), FixedError> {
+    map(values, out, |v| v.mul(scalar))
+}
+
+/// Divide each packed value by `scalar`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = divScalar))]
+pub fn div_scalar(values: &[u8], scalar: &I64F64, out: &mut [u8]) -> Result<(), FixedError> {
+    map(values, out, |v| v.div(scalar))
+}
+
+/// Calculate the exponential of each packed value.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = expMany))]
+pub fn exp_many(values: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    map(values, out, I64F64::exp)
+}
+
+/// Calculate the natural logarithm of each packed value.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = lnMany))]
+pub fn ln_many(values: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    map(values, out, I64F64::ln)
+}
+
+/// Calculate the square root of each packed value.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = sqrtMany))]
+pub fn sqrt_many(values: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    map(values, out, I64F64::sqrt)
+}
+
+/// A stack program applied to every element by [`map_formula`].
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    program: Vec<u8>,
+    constants: Vec<I64F64>,
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl Formula {
+    /// Create a formula from its opcodes and packed constants.
+    ///
+    /// The program must not pop an empty stack, grow it beyond [`MAX_STACK_DEPTH`],
+    /// or refer to a missing constant, and must leave exactly one value.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(constructor))]
+    pub fn new(program: &[u8], constants: &[u8]) -> Result<Formula, FixedError> {
+        let constants = unpack(constants)?;
+        let mut depth = 0usize;
+        let mut ops = program.iter();
+        while let Some(&op) = ops.next() {
+            let (pops, pushes) = match op {
+                X => (0, 1),
+                CONST => match ops.next() {
+                    Some(&i) if usize::from(i) < constants.len() => (0, 1),
+                    _ => return Err(FixedError::InvalidNumber),
+                },
+                ADD | SUB | MUL | DIV | POW => (2, 1),
+                EXP | LN | SQRT => (1, 1),
+                _ => return Err(FixedError::InvalidNumber),
+            };
+            depth = depth
+                .checked_sub(pops)
+                .ok_or(FixedError::Calculation("formula stack underflow"))?
+                + pushes;
+            if depth > MAX_STACK_DEPTH {
+                return Err(FixedError::Calculation("formula stack overflow"));
+            }
+        }
+        if depth != 1 {
+            return Err(FixedError::Calculation("formula must leave one value"));
+        }
+        Ok(Formula {
+            program: program.to_vec(),
+            constants,
+        })
+    }
+    /// Evaluate the formula at `x`.
+    pub fn evaluate(&self, x: &I64F64) -> Result<I64F64, FixedError> {
+        // the program was validated by `new`, so the stack never under- or overflows
+        let mut stack: [I64F64; MAX_STACK_DEPTH] =
+            core::array::from_fn(|_| I64F64::from_le_array([0; 16]));
+        let mut top = 0;
+        let mut ops = self.program.iter();
+        while let Some(&op) = ops.next() {
+            let value = match op {
+                X => x.clone(),
+                CONST => {
+                    let i = usize::from(*ops.next().expect("validated constant index"));
+                    self.constants[i].clone()
+                }
+                EXP | LN | SQRT => {
+                    top -= 1;
+                    let a = &stack[top];
+                    match op {
+                        EXP => a.exp()?,
+                        LN => a.ln()?,
+                        _ => a.sqrt()?,
+                    }
+                }
+                _ => {
+                    top -= 2;
+                    let (a, b) = (&stack[top], &stack[top + 1]);
+                    match op {
+                        ADD => a.add(b)?,
+                        SUB => a.sub(b)?,
+                        MUL => a.mul(b)?,
+                        DIV => a.div(b)?,
+                        _ => a.pow(b)?,
+                    }
+                }
+            };
+            stack[top] = value;
+            top += 1;
+        }
+        Ok(stack[0].clone())
+    }
+}
+
+/// Evaluate `formula` at each packed value.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = mapFormula))]
+pub fn map_formula(formula: &Formula, values: &[u8], out: &mut [u8]) -> Result<(), FixedError> {
+    map(values, out, |x| formula.evaluate(x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packed::pack;
+    use crate::test_util::{self, num};
+
+    fn nums(values: &[&str]) -> Vec<u8> {
+        pack(&test_util::nums(values))
+    }
+
+    fn unpacked(bytes: &[u8]) -> Vec<I64F64> {
+        unpack(bytes).unwrap()
+    }
+
+    #[test]
+    fn element_wise_matches_scalar() {
+        let a = nums(&["1.5", "-2.25", "0.1", "1000000.000001"]);
+        let b = nums(&["0.5", "3", "-0.7", "0.3"]);
+        let mut out = vec![0u8; a.len()];
+        let pairs = || unpacked(&a).into_iter().zip(unpacked(&b));
+
+        add_many(&a, &b, &mut out).unwrap();
+        let expected: Vec<_> = pairs().map(|(a, b)| a.add(&b).unwrap()).collect();
+        assert_eq!(unpacked(&out), expected);
+        mul_many(&a, &b, &mut out).unwrap();
+        let expected: Vec<_> = pairs().map(|(a, b)| a.mul(&b).unwrap()).collect();
+        assert_eq!(unpacked(&out), expected);
+        div_many(&a, &b, &mut out).unwrap();
+        let expected: Vec<_> = pairs().map(|(a, b)| a.div(&b).unwrap()).collect();
+        assert_eq!(unpacked(&out), expected);
+
+        let scalar = num("1.0001");
+        mul_scalar(&a, &scalar, &mut out).unwrap();
+        let expected: Vec<_> = unpacked(&a)
+            .iter()
+            .map(|v| v.mul(&scalar).unwrap())
+            .collect();
+        assert_eq!(unpacked(&out), expected);
+
+        let values = nums(&["0", "1", "-3.5", "10.25"]);
+        exp_many(&values, &mut out).unwrap();
+        let expected: Vec<_> = unpacked(&values).iter().map(|v| v.exp().unwrap()).collect();
+        assert_eq!(unpacked(&out), expected);
+    }
+
+    #[test]
+    fn errors_stop_the_batch() {
+        let a = nums(&["1", "2", "3"]);
+        let b = nums(&["1", "0", "1"]);
+        let mut out = vec![0u8; a.len()];
+        assert!(matches!(
+            div_many(&a, &b, &mut out),
+            Err(FixedError::Calculation("division by zero or overflow"))
+        ));
+        assert_eq!(unpacked(&out[..16]), [num("1")]);
+        assert_eq!(out[16..], [0u8; 32]);
+
+        let mut short = vec![0u8; 32];
+        assert!(matches!(
+            add_many(&a, &b, &mut short),
+            Err(FixedError::InvalidLength)
+        ));
+        assert!(matches!(
+            add_many(&a, &b[..32], &mut out),
+            Err(FixedError::InvalidLength)
+        ));
+        assert!(matches!(
+            exp_many(&a[..15], &mut out[..15]),
+            Err(FixedError::InvalidLength)
+        ));
+        assert!(exp_many(&[], &mut []).is_ok());
+    }
+
+    #[test]
+    fn formula_works() {
+        // sqrt(x) * 1.05 + e^(x / 2)
+        let program = [X, SQRT, CONST, 0, MUL, X, CONST, 1, DIV, EXP, ADD];
+        let formula = Formula::new(&program, &nums(&["1.05", "2"])).unwrap();
+        let values = nums(&["0", "1", "2.25", "7"]);
+        let mut out = vec![0u8; values.len()];
+        map_formula(&formula, &values, &mut out).unwrap();
+        let expected: Vec<_> = unpacked(&values)
+            .iter()
+            .map(|x| {
+                let a = x.sqrt().unwrap().mul(&num("1.05")).unwrap();
+                a.add(&x.div(&num("2")).unwrap().exp().unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(unpacked(&out), expected);
+
+        let ln = Formula::new(&[X, LN], &[]).unwrap();
+        assert!(map_formula(&ln, &nums(&["1", "-1"]), &mut out[..32]).is_err());
+        let pow = Formula::new(&[X, CONST, 0, POW, X, SUB], &nums(&["2"])).unwrap();
+        let x = num("3");
+        let expected = x.pow(&num("2")).unwrap().sub(&x).unwrap();
+        assert_eq!(pow.evaluate(&x).unwrap(), expected);
+    }
+
+    #[test]
+    fn invalid_formulas_are_rejected() {
+        let constants = nums(&["1"]);
+        for program in [
+            &[][..],
+            &[ADD],
+            &[X, X],
+            &[X, CONST],
+            &[X, CONST, 1, ADD],
+            &[X, 10],
+            &[X, EXP, MUL],
+            &[X; MAX_STACK_DEPTH + 1],
+        ] {
+            assert!(Formula::new(program, &constants).is_err(), "{:?}", program);
+        }
+        assert!(Formula::new(&[X], &constants[..15]).is_err());
+        let mut deep = [X; 2 * MAX_STACK_DEPTH - 1];
+        deep[MAX_STACK_DEPTH..].fill(ADD);
+        assert!(Formula::new(&deep, &[]).is_ok());
+    }
+}
@@ -0,0 +1,211 @@
+//! Compact variable-length encoding of [`I64F64`] values.
+//!
+//! The 16-byte little-endian form of [`I64F64::to_le_bytes`] spends most of its
+//! bytes on zeros for typical amounts and rates. The compact form keeps only
+//! the fractional bytes down to the lowest non-zero one and the integer bytes
+//! that the sign does not extend to, behind a one-byte header:
+//!
+//! | offset  | size | field                                                   |
+//! |---------|------|---------------------------------------------------------|
+//! | 0       | 1    | `f << 4 \| i`                                           |
+//! | 1       | f    | the `f` most significant fractional bytes, little endian |
+//! | 1 + f   | i    | the `i` least significant integer bytes, little endian   |
+//!
+//! Zero is the header alone, `5` and `0.5` take two bytes and a value with a
+//! full 64-bit fraction takes at most 17. Every value has exactly one
+//! encoding; [`decode`] rejects headers that are longer than needed.
+use crate::{types, FixedError, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// The size of the longest encoding.
+pub const MAX_ENCODED_LEN: usize = 17;
+
+/// The number of fractional and integer bytes the encoding of `value` keeps.
+fn lengths(value: types::I64F64) -> (usize, usize) {
+    let bits = value.to_bits();
+    let frac = (bits as u64).trailing_zeros() as usize / 8;
+    let int = (bits >> 64) as i64;
+    let int_len = if int == 0 {
+        0
+    } else {
+        // the magnitude bits plus a sign bit
+        let leading = if int < 0 { !int } else { int }.leading_zeros() as usize;
+        (64 - leading) / 8 + 1
+    };
+    (8 - frac, int_len)
+}
+
+/// Append the compact encoding of `value` to `out`.
+#[cfg(feature = "alloc")]
+pub fn encode(value: &I64F64, out: &mut Vec<u8>) {
+    let (frac_len, int_len) = lengths(value.inner);
+    let bytes = value.inner.to_le_bytes();
+    out.push(((frac_len << 4) | int_len) as u8);
+    out.extend_from_slice(&bytes[8 - frac_len..8]);
+    out.extend_from_slice(&bytes[8..8 + int_len]);
+}
+
+/// Decode a value from the start of `bytes`, returning it with the number of bytes read.
+pub fn decode(bytes: &[u8]) -> Result<(I64F64, usize), FixedError> {
+    let header = *bytes.first().ok_or(FixedError::InvalidLength)?;
+    let (frac_len, int_len) = (usize::from(header >> 4), usize::from(header & 0xf));
+    if frac_len > 8 || int_len > 8 {
+        return Err(FixedError::InvalidNumber);
+    }
+    let len = 1 + frac_len + int_len;
+    let body = bytes.get(1..len).ok_or(FixedError::InvalidLength)?;
+    let (frac, int) = body.split_at(frac_len);
+
+    let mut full = [0u8; 16];
+    full[8 - frac_len..8].copy_from_slice(frac);
+    full[8..8 + int_len].copy_from_slice(int);
+    if int.last().is_some_and(|b| b & 0x80 != 0) {
+        full[8 + int_len..].fill(0xff);
+    }
+    let inner = types::I64F64::from_le_bytes(full);
+    if lengths(inner) != (frac_len, int_len) {
+        return Err(FixedError::InvalidNumber);
+    }
+    Ok((I64F64 { inner }, len))
+}
+
+/// An [`I64F64`] that Borsh encodes in the compact form instead of 16 bytes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Compact(pub I64F64);
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for Compact {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        let mut bytes = Vec::with_capacity(MAX_ENCODED_LEN);
+        encode(&self.0, &mut bytes);
+        writer.write_all(&bytes)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for Compact {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let mut bytes = [0u8; MAX_ENCODED_LEN];
+        reader.read_exact(&mut bytes[..1])?;
+        let len = 1 + usize::from(bytes[0] >> 4) + usize::from(bytes[0] & 0xf);
+        let body = bytes.get_mut(1..len).ok_or_else(invalid_data)?;
+        reader.read_exact(body)?;
+        let (value, _) = decode(&bytes[..len]).map_err(|_| invalid_data())?;
+        Ok(Compact(value))
+    }
+}
+
+#[cfg(feature = "borsh")]
+fn invalid_data() -> borsh::io::Error {
+    borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, "invalid compact I64F64")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{num, samples};
+
+    fn encoded(value: &I64F64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encode(value, &mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn encode_works() {
+        assert_eq!(encoded(&num("0")), [0x00]);
+        assert_eq!(encoded(&num("5")), [0x01, 0x05]);
+        assert_eq!(encoded(&num("-1")), [0x01, 0xff]);
+        assert_eq!(encoded(&num("128")), [0x02, 0x80, 0x00]);
+        assert_eq!(encoded(&num("-128")), [0x01, 0x80]);
+        assert_eq!(encoded(&num("0.5")), [0x10, 0x80]);
+        assert_eq!(encoded(&num("-0.5")), [0x11, 0x80, 0xff]);
+        assert_eq!(encoded(&num("1.25")), [0x11, 0x40, 0x01]);
+        assert_eq!(encoded(&num("0.1")).len(), 9);
+        let min = I64F64::from(types::I64F64::MIN);
+        let max = I64F64::from(types::I64F64::MAX);
+        assert_eq!(encoded(&min), [0x08, 0, 0, 0, 0, 0, 0, 0, 0x80]);
+        assert_eq!(encoded(&max).len(), MAX_ENCODED_LEN);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut stream = Vec::new();
+        let values: Vec<I64F64> = samples(20_000).collect();
+        for value in &values {
+            let bytes = encoded(value);
+            assert!(bytes.len() <= MAX_ENCODED_LEN);
+            assert_eq!(decode(&bytes).unwrap(), (value.clone(), bytes.len()));
+            stream.extend_from_slice(&bytes);
+        }
+        // values decode one after another from a concatenated stream
+        let mut rest = stream.as_slice();
+        for value in &values {
+            let (decoded, len) = decode(rest).unwrap();
+            assert_eq!(&decoded, value);
+            rest = &rest[len..];
+        }
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn invalid_encodings() {
+        assert!(matches!(decode(&[]), Err(FixedError::InvalidLength)));
+        assert!(matches!(
+            decode(&[0x11, 0x40]),
+            Err(FixedError::InvalidLength)
+        ));
+        assert!(matches!(decode(&[0x09]), Err(FixedError::InvalidNumber)));
+        assert!(matches!(decode(&[0x90]), Err(FixedError::InvalidNumber)));
+        // longer than needed: a zero fractional byte and a redundant sign byte
+        assert!(matches!(
+            decode(&[0x10, 0x00]),
+            Err(FixedError::InvalidNumber)
+        ));
+        assert!(matches!(
+            decode(&[0x02, 0x05, 0x00]),
+            Err(FixedError::InvalidNumber)
+        ));
+        assert!(matches!(
+            decode(&[0x02, 0xff, 0xff]),
+            Err(FixedError::InvalidNumber)
+        ));
+        assert!(matches!(
+            decode(&[0x01, 0x00]),
+            Err(FixedError::InvalidNumber)
+        ));
+    }
+
+    #[test]
+    fn wrapper_methods_work() {
+        let value = num("-42.75");
+        let bytes = value.to_compact_bytes();
+        assert_eq!(bytes, [0x11, 0x40, 0xd5]);
+        assert_eq!(I64F64::from_compact_bytes(&bytes).unwrap(), value);
+        assert!(matches!(
+            I64F64::from_compact_bytes(&[0x01, 0x05, 0x00]),
+            Err(FixedError::InvalidLength)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "borsh")]
+    fn borsh_round_trip() {
+        for value in samples(20_000) {
+            let bytes = borsh::to_vec(&value).unwrap();
+            assert_eq!(bytes, value.to_le_bytes());
+            assert_eq!(borsh::from_slice::<I64F64>(&bytes).unwrap(), value);
+
+            let compact = Compact(value.clone());
+            let bytes = borsh::to_vec(&compact).unwrap();
+            assert_eq!(bytes, encoded(&value));
+            assert_eq!(borsh::from_slice::<Compact>(&bytes).unwrap(), compact);
+        }
+        assert!(borsh::from_slice::<I64F64>(&[0; 15]).is_err());
+        assert!(borsh::from_slice::<Compact>(&[0x10, 0x00]).is_err());
+        assert!(borsh::from_slice::<Compact>(&[0x90]).is_err());
+        // trailing bytes are left over
+        assert!(borsh::from_slice::<Compact>(&[0x01, 0x05, 0x00]).is_err());
+    }
+}
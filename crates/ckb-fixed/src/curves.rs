@@ -0,0 +1,438 @@
+//! Bonding curves for token launches.
+//!
+//! A bonding curve maps the token supply `s` to a spot price `p(s)`. Buying
+//! `amount` tokens at supply `s` costs `∫ p` from `s` to `s + amount`, and selling
+//! refunds `∫ p` from `s - amount` to `s`. Both integrals are evaluated in closed
+//! form by the same code, so buying and then selling the same amount returns
+//! exactly what was paid.
+//!
+//! A curve serializes to cell data as one kind byte followed by its parameters,
+//! each as 16 little-endian bytes:
+//!
+//! | kind | curve       | parameters                           |
+//! |------|-------------|--------------------------------------|
+//! | 0    | linear      | `slope`, `intercept`                 |
+//! | 1    | power-law   | `coefficient`, `exponent`            |
+//! | 2    | exponential | `scale`, `rate`                      |
+//! | 3    | sigmoid     | `max_price`, `steepness`, `midpoint` |
+use crate::math::powf;
+use crate::{transcendental, types, FixedError, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+const LINEAR: u8 = 0;
+const POWER_LAW: u8 = 1;
+const EXPONENTIAL: u8 = 2;
+const SIGMOID: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    /// `slope * s + intercept`
+    Linear {
+        slope: types::I64F64,
+        intercept: types::I64F64,
+    },
+    /// `coefficient * s^exponent`
+    PowerLaw {
+        coefficient: types::I64F64,
+        exponent: types::I64F64,
+    },
+    /// `scale * e^(rate * s)`
+    Exponential {
+        scale: types::I64F64,
+        rate: types::I64F64,
+    },
+    /// `max_price / (1 + e^(-steepness * (s - midpoint)))`
+    Sigmoid {
+        max_price: types::I64F64,
+        steepness: types::I64F64,
+        midpoint: types::I64F64,
+    },
+}
+
+fn overflow() -> FixedError {
+    FixedError::Calculation("bonding curve overflow")
+}
+
+fn exp(x: types::I64F64) -> Result<types::I64F64, FixedError> {
+    transcendental::exp_reduced(x).ok_or(FixedError::Calculation("exp calculation failed"))
+}
+
+/// `s^exponent` for `s >= 0`
+fn pow(s: types::I64F64, exponent: types::I64F64) -> Result<types::I64F64, FixedError> {
+    if exponent == types::I64F64::ZERO {
+        return Ok(types::I64F64::ONE);
+    }
+    if s == types::I64F64::ZERO {
+        return Ok(types::I64F64::ZERO);
+    }
+    powf(s, exponent).ok_or(FixedError::Calculation("pow calculation failed"))
+}
+
+/// `ln(1 + e^x)`, the antiderivative of the logistic function
+fn softplus(x: types::I64F64) -> Result<types::I64F64, FixedError> {
+    // ln(1 + e^x) = x + ln(1 + e^-x) keeps the exponential below one
+    let t = exp(-x.abs())?;
+    let r = transcendental::ln_normalized(types::I64F64::ONE + t)
+        .ok_or(FixedError::Calculation("ln calculation failed"))?;
+    if x > types::I64F64::ZERO {
+        r.checked_add(x).ok_or_else(overflow)
+    } else {
+        Ok(r)
+    }
+}
+
+/// A bonding curve with its parameters.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BondingCurve {
+    kind: Kind,
+}
+
+impl BondingCurve {
+    fn price_inner(&self, s: types::I64F64) -> Result<types::I64F64, FixedError> {
+        match self.kind {
+            Kind::Linear { slope, intercept } => slope
+                .checked_mul(s)
+                .and_then(|r| r.checked_add(intercept))
+                .ok_or_else(overflow),
+            Kind::PowerLaw {
+                coefficient,
+                exponent,
+            } => coefficient
+                .checked_mul(pow(s, exponent)?)
+                .ok_or_else(overflow),
+            Kind::Exponential { scale, rate } => {
+                let x = rate.checked_mul(s).ok_or_else(overflow)?;
+                scale.checked_mul(exp(x)?).ok_or_else(overflow)
+            }
+            Kind::Sigmoid {
+                max_price,
+                steepness,
+                midpoint,
+            } => {
+                let x = s
+                    .checked_sub(midpoint)
+                    .and_then(|r| r.checked_mul(steepness))
+                    .ok_or_else(overflow)?;
+                // 1 / (1 + e^-x), or e^x / (1 + e^x) when that would overflow
+                let t = exp(-x.abs())?;
+                let ratio = if x >= types::I64F64::ZERO {
+                    types::I64F64::ONE / (types::I64F64::ONE + t)
+                } else {
+                    t / (types::I64F64::ONE + t)
+                };
+                max_price.checked_mul(ratio).ok_or_else(overflow)
+            }
+        }
+    }
+
+    /// The antiderivative of the price, up to a constant.
+    fn antiderivative(&self, s: types::I64F64) -> Result<types::I64F64, FixedError> {
+        match self.kind {
+            Kind::Linear { slope, intercept } => {
+                let half_s2 = s.checked_mul(s).ok_or_else(overflow)? / 2;
+                slope
+                    .checked_mul(half_s2)
+                    .and_then(|r| r.checked_add(intercept.checked_mul(s)?))
+                    .ok_or_else(overflow)
+            }
+            Kind::PowerLaw {
+                coefficient,
+                exponent,
+            } => {
+                let n1 = exponent
+                    .checked_add(types::I64F64::ONE)
+                    .ok_or_else(overflow)?;
+                pow(s, n1)?
+                    .checked_div(n1)
+                    .and_then(|r| r.checked_mul(coefficient))
+                    .ok_or_else(overflow)
+            }
+            Kind::Exponential { scale, rate } => {
+                let x = rate.checked_mul(s).ok_or_else(overflow)?;
+                exp(x)?
+                    .checked_div(rate)
+                    .and_then(|r| r.checked_mul(scale))
+                    .ok_or_else(overflow)
+            }
+            Kind::Sigmoid {
+                max_price,
+                steepness,
+                midpoint,
+            } => {
+                let x = s
+                    .checked_sub(midpoint)
+                    .and_then(|r| r.checked_mul(steepness))
+                    .ok_or_else(overflow)?;
+                softplus(x)?
+                    .checked_div(steepness)
+                    .and_then(|r| r.checked_mul(max_price))
+                    .ok_or_else(overflow)
+            }
+        }
+    }
+
+    fn integral(&self, from: types::I64F64, to: types::I64F64) -> Result<I64F64, FixedError> {
+        if from < types::I64F64::ZERO || to < from {
+            return Err(FixedError::Calculation("invalid supply range"));
+        }
+        let inner = self
+            .antiderivative(to)?
+            .checked_sub(self.antiderivative(from)?)
+            .ok_or_else(overflow)?;
+        Ok(I64F64 { inner })
+    }
+
+    #[cfg(feature = "alloc")]
+    fn params(&self) -> (u8, [types::I64F64; 3], usize) {
+        let zero = types::I64F64::ZERO;
+        match self.kind {
+            Kind::Linear { slope, intercept } => (LINEAR, [slope, intercept, zero], 2),
+            Kind::PowerLaw {
+                coefficient,
+                exponent,
+            } => (POWER_LAW, [coefficient, exponent, zero], 2),
+            Kind::Exponential { scale, rate } => (EXPONENTIAL, [scale, rate, zero], 2),
+            Kind::Sigmoid {
+                max_price,
+                steepness,
+                midpoint,
+            } => (SIGMOID, [max_price, steepness, midpoint], 3),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl BondingCurve {
+    /// Create a linear curve, `slope * s + intercept`, with a non-negative `slope`.
+    pub fn linear(slope: &I64F64, intercept: &I64F64) -> Result<BondingCurve, FixedError> {
+        if slope.inner < types::I64F64::ZERO {
+            return Err(FixedError::Calculation("slope must not be negative"));
+        }
+        Ok(BondingCurve {
+            kind: Kind::Linear {
+                slope: slope.inner,
+                intercept: intercept.inner,
+            },
+        })
+    }
+    /// Create a power-law curve, `coefficient * s^exponent`, with a non-negative `exponent`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = powerLaw))]
+    pub fn power_law(coefficient: &I64F64, exponent: &I64F64) -> Result<BondingCurve, FixedError> {
+        if exponent.inner < types::I64F64::ZERO {
+            return Err(FixedError::Calculation("exponent must not be negative"));
+        }
+        Ok(BondingCurve {
+            kind: Kind::PowerLaw {
+                coefficient: coefficient.inner,
+                exponent: exponent.inner,
+            },
+        })
+    }
+    /// Create an exponential curve, `scale * e^(rate * s)`, with a non-zero `rate`.
+    pub fn exponential(scale: &I64F64, rate: &I64F64) -> Result<BondingCurve, FixedError> {
+        if rate.inner == types::I64F64::ZERO {
+            return Err(FixedError::Calculation("rate must not be zero"));
+        }
+        Ok(BondingCurve {
+            kind: Kind::Exponential {
+                scale: scale.inner,
+                rate: rate.inner,
+            },
+        })
+    }
+    /// Create a sigmoid curve, `max_price / (1 + e^(-steepness * (s - midpoint)))`, with a
+    /// positive `steepness`.
+    pub fn sigmoid(
+        max_price: &I64F64,
+        steepness: &I64F64,
+        midpoint: &I64F64,
+    ) -> Result<BondingCurve, FixedError> {
+        if steepness.inner <= types::I64F64::ZERO {
+            return Err(FixedError::Calculation("steepness must be positive"));
+        }
+        Ok(BondingCurve {
+            kind: Kind::Sigmoid {
+                max_price: max_price.inner,
+                steepness: steepness.inner,
+                midpoint: midpoint.inner,
+            },
+        })
+    }
+    /// Calculate the spot price at `supply`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = spotPrice))]
+    pub fn spot_price(&self, supply: &I64F64) -> Result<I64F64, FixedError> {
+        if supply.inner < types::I64F64::ZERO {
+            return Err(FixedError::Calculation("invalid supply range"));
+        }
+        let inner = self.price_inner(supply.inner)?;
+        Ok(I64F64 { inner })
+    }
+    /// Calculate the cost of buying `amount` tokens at `supply`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = buyCost))]
+    pub fn buy_cost(&self, supply: &I64F64, amount: &I64F64) -> Result<I64F64, FixedError> {
+        let to = supply
+            .inner
+            .checked_add(amount.inner)
+            .ok_or_else(overflow)?;
+        self.integral(supply.inner, to)
+    }
+    /// Calculate the refund for selling `amount` tokens at `supply`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = sellRefund))]
+    pub fn sell_refund(&self, supply: &I64F64, amount: &I64F64) -> Result<I64F64, FixedError> {
+        let from = supply
+            .inner
+            .checked_sub(amount.inner)
+            .ok_or_else(overflow)?;
+        self.integral(from, supply.inner)
+    }
+    /// Serialize the curve to bytes for cell data.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toBytes))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (kind, params, count) = self.params();
+        let mut bytes = Vec::with_capacity(1 + 16 * count);
+        bytes.push(kind);
+        for param in &params[..count] {
+            bytes.extend_from_slice(&param.to_le_bytes());
+        }
+        bytes
+    }
+    /// Deserialize a curve from bytes produced by `to_bytes`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromBytes))]
+    pub fn from_bytes(bytes: &[u8]) -> Result<BondingCurve, FixedError> {
+        let (kind, params) = bytes.split_first().ok_or(FixedError::InvalidLength)?;
+        let param = |i: usize| I64F64::new(&params[16 * i..16 * (i + 1)]);
+        let count = match *kind {
+            LINEAR | POWER_LAW | EXPONENTIAL => 2,
+            SIGMOID => 3,
+            _ => return Err(FixedError::InvalidNumber),
+        };
+        if params.len() != 16 * count {
+            return Err(FixedError::InvalidLength);
+        }
+        match *kind {
+            LINEAR => Self::linear(&param(0)?, &param(1)?),
+            POWER_LAW => Self::power_law(&param(0)?, &param(1)?),
+            EXPONENTIAL => Self::exponential(&param(0)?, &param(1)?),
+            _ => Self::sigmoid(&param(0)?, &param(1)?, &param(2)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+    use approx::assert_relative_eq;
+    use fixed::traits::LossyInto;
+
+    fn to_f64(v: &I64F64) -> f64 {
+        v.inner.lossy_into()
+    }
+
+    fn curves() -> Vec<BondingCurve> {
+        vec![
+            BondingCurve::linear(&num("0.001"), &num("1")).unwrap(),
+            BondingCurve::power_law(&num("0.5"), &num("1.5")).unwrap(),
+            BondingCurve::exponential(&num("0.01"), &num("0.0005")).unwrap(),
+            BondingCurve::sigmoid(&num("10"), &num("0.002"), &num("5000")).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn spot_price_works() {
+        let s = 1234.5_f64;
+        let expected = [
+            0.001 * s + 1.0,
+            0.5 * s.powf(1.5),
+            0.01 * (0.0005 * s).exp(),
+            10.0 / (1.0 + (-0.002 * (s - 5000.0)).exp()),
+        ];
+        for (curve, expected) in curves().iter().zip(expected) {
+            let price = curve.spot_price(&num("1234.5")).unwrap();
+            assert_relative_eq!(to_f64(&price), expected, max_relative = 1.0e-12);
+        }
+        let curve = BondingCurve::power_law(&num("2"), &num("0")).unwrap();
+        assert_eq!(curve.spot_price(&num("0")).unwrap(), num("2"));
+    }
+
+    #[test]
+    fn buy_cost_works() {
+        let (a, b) = (1000.0_f64, 3500.0_f64);
+        let softplus = |x: f64| x.exp().ln_1p();
+        let expected = [
+            0.001 * (b * b - a * a) / 2.0 + (b - a),
+            0.5 * (b.powf(2.5) - a.powf(2.5)) / 2.5,
+            0.01 * ((0.0005 * b).exp() - (0.0005 * a).exp()) / 0.0005,
+            10.0 * (softplus(0.002 * (b - 5000.0)) - softplus(0.002 * (a - 5000.0))) / 0.002,
+        ];
+        for (curve, expected) in curves().iter().zip(expected) {
+            let cost = curve.buy_cost(&num("1000"), &num("2500")).unwrap();
+            assert_relative_eq!(to_f64(&cost), expected, max_relative = 1.0e-12);
+            let refund = curve.sell_refund(&num("3500"), &num("2500")).unwrap();
+            assert_eq!(cost, refund);
+        }
+    }
+
+    #[test]
+    fn large_exponent() {
+        // rate * supply = 30, where a plain Taylor series for `exp` loses precision
+        let curve = BondingCurve::exponential(&num("0.0009765625"), &num("0.01")).unwrap();
+        let price = curve.spot_price(&num("3000")).unwrap();
+        assert_relative_eq!(
+            to_f64(&price),
+            10_436_010_333.519_983,
+            max_relative = 1.0e-14
+        );
+        let cost = curve.buy_cost(&num("2900"), &num("100")).unwrap();
+        let expected = (30.0_f64.exp() - 29.0_f64.exp()) / 1024.0 / 0.01;
+        assert_relative_eq!(to_f64(&cost), expected, max_relative = 1.0e-12);
+
+        let curve = BondingCurve::sigmoid(&num("10"), &num("0.01"), &num("0")).unwrap();
+        let price = curve.spot_price(&num("4000")).unwrap();
+        assert_relative_eq!(
+            to_f64(&price),
+            10.0 / (1.0 + (-40.0_f64).exp()),
+            max_relative = 1.0e-14
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        let curve = &curves()[0];
+        assert!(curve.sell_refund(&num("10"), &num("11")).is_err());
+        assert!(curve.buy_cost(&num("10"), &num("-1")).is_err());
+        assert!(curve.spot_price(&num("-1")).is_err());
+        assert!(BondingCurve::linear(&num("-1"), &num("1")).is_err());
+        assert!(BondingCurve::power_law(&num("1"), &num("-1")).is_err());
+        assert!(BondingCurve::exponential(&num("1"), &num("0")).is_err());
+        assert!(BondingCurve::sigmoid(&num("1"), &num("0"), &num("0")).is_err());
+        let curve = BondingCurve::exponential(&num("1"), &num("1")).unwrap();
+        assert!(curve.buy_cost(&num("0"), &num("100")).is_err());
+        // an exponent decoded from cell data may leave no room for `exponent + 1`
+        let max = I64F64::from(types::I64F64::MAX);
+        let curve = BondingCurve::power_law(&num("1"), &max).unwrap();
+        let curve = BondingCurve::from_bytes(&curve.to_bytes()).unwrap();
+        assert!(curve.buy_cost(&num("1"), &num("1")).is_err());
+        assert!(curve.sell_refund(&num("2"), &num("1")).is_err());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        for curve in curves() {
+            let bytes = curve.to_bytes();
+            assert_eq!(BondingCurve::from_bytes(&bytes).unwrap(), curve);
+            assert!(BondingCurve::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        }
+        let bytes = curves()[3].to_bytes();
+        assert_eq!(bytes.len(), 49);
+        assert_eq!(bytes[0], SIGMOID);
+        assert_eq!(&bytes[1..17], num("10").to_le_bytes().as_slice());
+        assert!(BondingCurve::from_bytes(&[]).is_err());
+        assert!(BondingCurve::from_bytes(&[4; 33]).is_err());
+    }
+}
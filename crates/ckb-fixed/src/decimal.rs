@@ -0,0 +1,212 @@
+//! Canonical fixed-width decimal strings of [`I64F64`] values.
+//!
+//! Some configuration cells store values as ASCII decimals so they can be read
+//! without tooling. For byte comparison to mean value comparison, every value
+//! must have exactly one string for a given number of decimal places:
+//!
+//! - an optional `-`, never on a value that rounds to zero
+//! - the integer part without leading zeros, `0` when it is zero
+//! - a `.` followed by exactly `places` digits, or nothing when `places` is 0
+//!
+//! Values are rounded to `places` digits, half to even. [`decode`] accepts
+//! exactly the strings [`encode`] produces and rejects anything else, such as
+//! `+1.00`, `01.00`, `1.0` or `-0.00` with two places. Neither needs an
+//! allocator.
+use crate::{types, FixedError, I64F64};
+
+/// The most decimal places, enough to write any `I64F64` exactly.
+pub const MAX_PLACES: u32 = 64;
+
+/// The length of the longest string: a sign, 19 integer digits, a point and [`MAX_PLACES`] digits.
+pub const MAX_LEN: usize = 85;
+
+fn check_places(places: u32) -> Result<usize, FixedError> {
+    if places > MAX_PLACES {
+        return Err(FixedError::Calculation("too many decimal places"));
+    }
+    Ok(places as usize)
+}
+
+/// Write `value` rounded to `places` decimal places into `buf`, returning the written string.
+///
+/// Fails with `Calculation("too many decimal places")` beyond [`MAX_PLACES`], and with
+/// `Calculation("decimal overflow")` when a value within half a unit in the last place of
+/// `I64F64::MAX` rounds up to `2^63`, which [`decode`] couldn't read back; `MAX` with 2 places
+/// is one.
+pub fn encode<'a>(
+    value: &I64F64,
+    places: u32,
+    buf: &'a mut [u8; MAX_LEN],
+) -> Result<&'a str, FixedError> {
+    let places = check_places(places)?;
+    let bits = value.inner.to_bits();
+    let magnitude = bits.unsigned_abs();
+    let (mut int, mut frac) = (magnitude >> 64, magnitude as u64);
+
+    // the fractional digits are exact, each step shifts one digit into the integer bits
+    let mut digits = [0u8; MAX_PLACES as usize];
+    for digit in digits[..places].iter_mut() {
+        let scaled = u128::from(frac) * 10;
+        *digit = (scaled >> 64) as u8;
+        frac = scaled as u64;
+    }
+    let half = 1u64 << 63;
+    let odd = match places {
+        0 => int & 1 == 1,
+        _ => digits[places - 1] & 1 == 1,
+    };
+    if frac > half || (frac == half && odd) {
+        match digits[..places].iter().rposition(|d| *d != 9) {
+            Some(i) => {
+                digits[i] += 1;
+                digits[i + 1..places].fill(0);
+            }
+            None => {
+                digits[..places].fill(0);
+                int += 1;
+            }
+        }
+    }
+    // only the negative end of the range reaches 2^63
+    if bits >= 0 && int >> 63 != 0 {
+        return Err(FixedError::Calculation("decimal overflow"));
+    }
+    let negative = bits < 0 && (int != 0 || digits[..places].iter().any(|d| *d != 0));
+
+    let mut int_digits = [0u8; 19];
+    let mut count = 0;
+    loop {
+        int_digits[count] = b'0' + (int % 10) as u8;
+        int /= 10;
+        count += 1;
+        if int == 0 {
+            break;
+        }
+    }
+    let mut len = 0;
+    let mut push = |byte: u8| {
+        buf[len] = byte;
+        len += 1;
+    };
+    if negative {
+        push(b'-');
+    }
+    int_digits[..count].iter().rev().for_each(|d| push(*d));
+    if places > 0 {
+        push(b'.');
+        digits[..places].iter().for_each(|d| push(b'0' + d));
+    }
+    core::str::from_utf8(&buf[..len]).map_err(|_| FixedError::InvalidNumber)
+}
+
+/// Parse the canonical string of a value with `places` decimal places.
+pub fn decode(bytes: &[u8], places: u32) -> Result<I64F64, FixedError> {
+    check_places(places)?;
+    if bytes.len() > MAX_LEN {
+        return Err(FixedError::InvalidLength);
+    }
+    let s = core::str::from_utf8(bytes).map_err(|_| FixedError::InvalidNumber)?;
+    let inner = types::I64F64::from_str(s).map_err(|_| FixedError::InvalidNumber)?;
+    let value = I64F64 { inner };
+    // anything but the one string of the value it parses to is not canonical
+    let mut buf = [0u8; MAX_LEN];
+    match encode(&value, places, &mut buf) {
+        Ok(canonical) if canonical.as_bytes() == bytes => Ok(value),
+        _ => Err(FixedError::InvalidNumber),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{num, samples};
+
+    fn encoded(value: &I64F64, places: u32) -> String {
+        let mut buf = [0u8; MAX_LEN];
+        encode(value, places, &mut buf).unwrap().to_string()
+    }
+
+    #[test]
+    fn encode_works() {
+        assert_eq!(encoded(&num("0"), 0), "0");
+        assert_eq!(encoded(&num("0"), 2), "0.00");
+        assert_eq!(encoded(&num("12"), 3), "12.000");
+        assert_eq!(encoded(&num("-1.5"), 1), "-1.5");
+        assert_eq!(encoded(&num("0.1"), 4), "0.1000");
+        assert_eq!(encoded(&num("0.003"), 6), "0.003000");
+        assert_eq!(encoded(&num("1.999"), 2), "2.00");
+        assert_eq!(encoded(&num("-99.996"), 2), "-100.00");
+        // half to even
+        assert_eq!(encoded(&num("0.125"), 2), "0.12");
+        assert_eq!(encoded(&num("0.375"), 2), "0.38");
+        assert_eq!(encoded(&num("2.5"), 0), "2");
+        assert_eq!(encoded(&num("-3.5"), 0), "-4");
+        // no negative zero
+        assert_eq!(encoded(&num("-0.001"), 2), "0.00");
+        assert_eq!(encoded(&num("-0.5"), 0), "0");
+
+        let delta = I64F64::from(types::I64F64::DELTA);
+        assert_eq!(
+            encoded(&delta, MAX_PLACES),
+            "0.0000000000000000000542101086242752217003726400434970855712890625"
+        );
+        let min = I64F64::from(types::I64F64::MIN);
+        assert_eq!(encoded(&min, 2), "-9223372036854775808.00");
+        assert_eq!(encoded(&min, MAX_PLACES).len(), MAX_LEN);
+        let max = I64F64::from(types::I64F64::MAX);
+        let mut buf = [0u8; MAX_LEN];
+        assert!(encode(&max, 2, &mut buf).is_err());
+        assert_eq!(encoded(&max, 19), "9223372036854775807.9999999999999999999");
+        assert!(encode(&max, MAX_PLACES + 1, &mut buf).is_err());
+    }
+
+    #[test]
+    fn decode_works() {
+        assert_eq!(decode(b"0", 0).unwrap(), num("0"));
+        assert_eq!(decode(b"-1.50", 2).unwrap(), num("-1.5"));
+        assert_eq!(decode(b"0.100000", 6).unwrap(), num("0.1"));
+        assert_eq!(
+            decode(b"-9223372036854775808.000", 3).unwrap(),
+            I64F64::from(types::I64F64::MIN)
+        );
+        for s in [
+            "",
+            "-",
+            ".",
+            "1.",
+            ".50",
+            "+1.50",
+            "01.50",
+            "1.5",
+            "1.500",
+            "-0.00",
+            "1,50",
+            " 1.50",
+            "1.50 ",
+            "1e2",
+            "9223372036854775808.00",
+        ] {
+            assert!(decode(s.as_bytes(), 2).is_err(), "{s}");
+        }
+        assert!(decode(b"1.0", 0).is_err());
+        assert!(decode(b"1", 1).is_err());
+        assert!(decode(b"1.00", MAX_PLACES + 1).is_err());
+        // a string finer than the precision is not the string of the value it parses to
+        let tiny = format!("0.{}1", "0".repeat(28));
+        assert!(decode(tiny.as_bytes(), 30).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        for value in samples(2_000) {
+            for places in [0, 1, 2, 6, 9, 18, 19, 20, 40, MAX_PLACES] {
+                let s = encoded(&value, places);
+                let parsed = decode(s.as_bytes(), places).unwrap();
+                assert_eq!(encoded(&parsed, places), s);
+                if places == MAX_PLACES {
+                    assert_eq!(parsed, value);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Loan amortization and annuities.
+//!
+//! `rate` is the interest rate per payment period and payments fall at the end
+//! of each period. Amounts in an [`AmortizationSchedule`] are rounded to whole
+//! units, so express them in the smallest unit of the currency (e.g. cents or
+//! shannons).
+use crate::math::powu;
+use crate::{types, FixedError, I64F64};
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+fn check_terms(rate: types::I64F64, periods: u32) -> Result<(), FixedError> {
+    if rate < types::I64F64::ZERO {
+        return Err(FixedError::Calculation("rate must not be negative"));
+    }
+    if periods == 0 {
+        return Err(FixedError::Calculation("periods must be positive"));
+    }
+    Ok(())
+}
+
+/// `(1 + rate)^periods`
+fn growth(rate: types::I64F64, periods: u32) -> Result<types::I64F64, FixedError> {
+    let base = rate
+        .checked_add(types::I64F64::ONE)
+        .ok_or(FixedError::Calculation("rate overflow"))?;
+    powu(base, periods.into(), false).ok_or(FixedError::Calculation("growth overflow"))
+}
+
+fn payment_inner(
+    principal: types::I64F64,
+    rate: types::I64F64,
+    periods: u32,
+) -> Result<types::I64F64, FixedError> {
+    check_terms(rate, periods)?;
+    if rate == types::I64F64::ZERO {
+        return Ok(principal / types::I64F64::from_num(periods));
+    }
+    let g = growth(rate, periods)?;
+    // rate / (1 - (1 + rate)^-periods), rearranged to avoid the reciprocal
+    rate.checked_mul(g)
+        .and_then(|r| r.checked_div(g - types::I64F64::ONE))
+        .and_then(|r| r.checked_mul(principal))
+        .ok_or(FixedError::Calculation("payment overflow"))
+}
+
+/// Calculate the payment per period that repays `principal` over `periods` periods.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+pub fn payment(principal: &I64F64, rate: &I64F64, periods: u32) -> Result<I64F64, FixedError> {
+    let inner = payment_inner(principal.inner, rate.inner, periods)?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the present value of `periods` payments of `payment`,
+/// `payment * (1 - (1 + rate)^-periods) / rate`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = presentValue))]
+pub fn present_value(payment: &I64F64, rate: &I64F64, periods: u32) -> Result<I64F64, FixedError> {
+    check_terms(rate.inner, periods)?;
+    let payment = payment.inner;
+    let inner = if rate.inner == types::I64F64::ZERO {
+        payment.checked_mul_int(periods.into())
+    } else {
+        let g = growth(rate.inner, periods)?;
+        (g - types::I64F64::ONE)
+            .checked_div(g)
+            .and_then(|r| r.checked_div(rate.inner))
+            .and_then(|r| r.checked_mul(payment))
+    }
+    .ok_or(FixedError::Calculation("present value overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the future value of `periods` payments of `payment`,
+/// `payment * ((1 + rate)^periods - 1) / rate`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = futureValue))]
+pub fn future_value(payment: &I64F64, rate: &I64F64, periods: u32) -> Result<I64F64, FixedError> {
+    check_terms(rate.inner, periods)?;
+    let payment = payment.inner;
+    let inner = if rate.inner == types::I64F64::ZERO {
+        payment.checked_mul_int(periods.into())
+    } else {
+        let g = growth(rate.inner, periods)?;
+        (g - types::I64F64::ONE)
+            .checked_div(rate.inner)
+            .and_then(|r| r.checked_mul(payment))
+    }
+    .ok_or(FixedError::Calculation("future value overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// One installment of an [`AmortizationSchedule`].
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Installment {
+    period: u32,
+    payment: types::I64F64,
+    interest: types::I64F64,
+    principal: types::I64F64,
+    balance: types::I64F64,
+}
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl Installment {
+    /// The 1-based period number.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn period(&self) -> u32 {
+        self.period
+    }
+    /// The amount paid in this period, `interest + principal`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn payment(&self) -> I64F64 {
+        self.payment.into()
+    }
+    /// The interest part of the payment.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn interest(&self) -> I64F64 {
+        self.interest.into()
+    }
+    /// The principal part of the payment.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn principal(&self) -> I64F64 {
+        self.principal.into()
+    }
+    /// The outstanding balance after the payment.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn balance(&self) -> I64F64 {
+        self.balance.into()
+    }
+}
+
+/// Iterator over the installments of a fully amortizing loan.
+///
+/// Every installment but the last pays the level payment rounded to the nearest
+/// whole unit, with the interest part also rounded to the nearest whole unit.
+/// The last installment pays off the remaining balance, absorbing all rounding
+/// residue, so the balance always ends at exactly zero.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone)]
+pub struct AmortizationSchedule {
+    rate: types::I64F64,
+    periods: u32,
+    payment: types::I64F64,
+    period: u32,
+    balance: types::I64F64,
+}
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl AmortizationSchedule {
+    /// Create the schedule repaying `principal` over `periods` periods.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(constructor))]
+    pub fn new(principal: &I64F64, rate: &I64F64, periods: u32) -> Result<Self, FixedError> {
+        let principal = principal.inner;
+        if principal < types::I64F64::ZERO {
+            return Err(FixedError::Calculation("principal must not be negative"));
+        }
+        let payment = payment_inner(principal, rate.inner, periods)?.round();
+        // The balance never grows and the interest never exceeds `principal * rate`,
+        // so this bounds every intermediate value of the schedule.
+        principal
+            .checked_mul(rate.inner)
+            .and_then(|r| r.round().checked_add(principal))
+            .ok_or(FixedError::Calculation("schedule overflow"))?;
+        Ok(Self {
+            rate: rate.inner,
+            periods,
+            payment,
+            period: 0,
+            balance: principal,
+        })
+    }
+    /// The level payment of every installment but the last.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn payment(&self) -> I64F64 {
+        self.payment.into()
+    }
+    /// The next installment, or `None` once the loan is repaid.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = next))]
+    pub fn next_installment(&mut self) -> Option<Installment> {
+        if self.period == self.periods {
+            return None;
+        }
+        self.period += 1;
+        let interest = (self.balance * self.rate).round();
+        let payment = if self.period == self.periods {
+            self.balance + interest
+        } else {
+            self.payment.min(self.balance + interest)
+        };
+        let principal = payment - interest;
+        self.balance -= principal;
+        Some(Installment {
+            period: self.period,
+            payment,
+            interest,
+            principal,
+            balance: self.balance,
+        })
+    }
+}
+
+impl Iterator for AmortizationSchedule {
+    type Item = Installment;
+
+    fn next(&mut self) -> Option<Installment> {
+        self.next_installment()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+    use approx::assert_relative_eq;
+    use fixed::traits::LossyInto;
+
+    fn to_f64(v: &I64F64) -> f64 {
+        v.inner.lossy_into()
+    }
+
+    #[test]
+    fn payment_works() {
+        // 30 years at 6% a year, paid monthly
+        let rate = num("0.005");
+        let result = payment(&num("200000"), &rate, 360).unwrap();
+        assert_relative_eq!(to_f64(&result), 1199.1010503055, max_relative = 1.0e-12);
+
+        let result = payment(&num("1200"), &num("0"), 12).unwrap();
+        assert_eq!(result, num("100"));
+        assert!(payment(&num("1200"), &rate, 0).is_err());
+        assert!(payment(&num("1200"), &num("-0.01"), 12).is_err());
+    }
+
+    #[test]
+    fn present_and_future_value_works() {
+        let rate = num("0.005");
+        let pmt = payment(&num("200000"), &rate, 360).unwrap();
+        let pv = present_value(&pmt, &rate, 360).unwrap();
+        assert_relative_eq!(to_f64(&pv), 200000.0, max_relative = 1.0e-14);
+
+        let fv = future_value(&num("100"), &num("0.01"), 12).unwrap();
+        let expected = 100.0 * (1.01_f64.powi(12) - 1.0) / 0.01;
+        assert_relative_eq!(to_f64(&fv), expected, max_relative = 1.0e-14);
+
+        assert_eq!(
+            present_value(&num("100"), &num("0"), 12).unwrap(),
+            num("1200")
+        );
+        assert_eq!(
+            future_value(&num("100"), &num("0"), 12).unwrap(),
+            num("1200")
+        );
+    }
+
+    #[test]
+    fn schedule_works() {
+        let principal = num("2000000");
+        let rate = num("0.0075");
+        let schedule = AmortizationSchedule::new(&principal, &rate, 24).unwrap();
+        let level = schedule.payment();
+        assert_eq!(level, num("91369"));
+
+        let installments: Vec<Installment> = schedule.collect();
+        assert_eq!(installments.len(), 24);
+        let mut total_principal = num("0");
+        for (i, installment) in installments.iter().enumerate() {
+            assert_eq!(installment.period(), i as u32 + 1);
+            assert_eq!(installment.interest(), installment.interest().round());
+            assert_eq!(
+                installment.payment(),
+                installment
+                    .interest()
+                    .add(&installment.principal())
+                    .unwrap()
+            );
+            if i + 1 < installments.len() {
+                assert_eq!(installment.payment(), level);
+            }
+            total_principal = total_principal.add(&installment.principal()).unwrap();
+        }
+        assert_eq!(total_principal, principal);
+        let last = installments.last().unwrap();
+        assert_eq!(last.balance(), num("0"));
+        assert_ne!(last.payment(), level);
+    }
+
+    #[test]
+    fn schedule_zero_rate() {
+        let schedule = AmortizationSchedule::new(&num("1000"), &num("0"), 3).unwrap();
+        let payments: Vec<I64F64> = schedule.map(|i| i.payment()).collect();
+        assert_eq!(payments, vec![num("333"), num("333"), num("334")]);
+    }
+}
@@ -0,0 +1,230 @@
+//! Interest rates and compounding.
+//!
+//! Rates are annual fractions, e.g. `0.05` for 5%. Functions returning an
+//! amount take a `round_up` flag so that a protocol can round in its own favour:
+//! up for debt owed to it, down for interest paid out by it. The flag directs
+//! the rate division and every multiplication, including the repeated squaring
+//! of whole periods; fractional powers and `exp` themselves are not directed.
+use crate::math::{powf, powu};
+use crate::rounding::{div_up, mul_up};
+use crate::{transcendental, types, FixedError, I64F64};
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+fn check_non_negative(value: types::I64F64, msg: &'static str) -> Result<(), FixedError> {
+    if value < types::I64F64::ZERO {
+        return Err(FixedError::Calculation(msg));
+    }
+    Ok(())
+}
+
+/// `1 + rate / periods`
+fn periodic_factor(
+    rate: types::I64F64,
+    periods: u32,
+    round_up: bool,
+) -> Result<types::I64F64, FixedError> {
+    check_non_negative(rate, "rate must not be negative")?;
+    if periods == 0 {
+        return Err(FixedError::Calculation("periods must be positive"));
+    }
+    let periods = types::I64F64::from_num(periods);
+    let periodic_rate = if round_up {
+        div_up(rate, periods)
+    } else {
+        rate.checked_div(periods)
+    }
+    .ok_or(FixedError::Calculation("rate division overflow"))?;
+    periodic_rate
+        .checked_add(types::I64F64::ONE)
+        .ok_or(FixedError::Calculation("rate overflow"))
+}
+
+/// `(1 + rate)^(1 / periods) - 1`
+fn per_period_rate_inner(rate: types::I64F64, periods: u32) -> Result<types::I64F64, FixedError> {
+    check_non_negative(rate, "rate must not be negative")?;
+    if periods == 0 {
+        return Err(FixedError::Calculation("periods must be positive"));
+    }
+    let base = rate
+        .checked_add(types::I64F64::ONE)
+        .ok_or(FixedError::Calculation("rate overflow"))?;
+    let exponent = types::I64F64::ONE / types::I64F64::from_num(periods);
+    let factor = powf(base, exponent).ok_or(FixedError::Calculation("pow calculation failed"))?;
+    // `powf` may undershoot one by an ULP for tiny rates
+    Ok((factor - types::I64F64::ONE).max(types::I64F64::ZERO))
+}
+
+/// Calculate the amount `principal * (1 + rate / periods_per_year)^(periods_per_year * years)`
+/// after compounding `periods_per_year` times a year.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = compoundInterest))]
+pub fn compound_interest(
+    principal: &I64F64,
+    rate: &I64F64,
+    periods_per_year: u32,
+    years: &I64F64,
+    round_up: bool,
+) -> Result<I64F64, FixedError> {
+    check_non_negative(principal.inner, "principal must not be negative")?;
+    check_non_negative(years.inner, "years must not be negative")?;
+    let base = periodic_factor(rate.inner, periods_per_year, round_up)?;
+    let periods = years
+        .inner
+        .checked_mul_int(periods_per_year.into())
+        .ok_or(FixedError::Calculation("periods overflow"))?;
+    let mul: fn(_, _) -> _ = if round_up {
+        mul_up
+    } else {
+        types::I64F64::checked_mul
+    };
+    // whole periods go through repeated squaring, each product rounded by `round_up`;
+    // only the remainder needs `pow`
+    let mut factor = powu(base, periods.int().to_num(), round_up)
+        .ok_or(FixedError::Calculation("compound overflow"))?;
+    if periods.frac() != types::I64F64::ZERO {
+        let partial =
+            powf(base, periods.frac()).ok_or(FixedError::Calculation("pow calculation failed"))?;
+        factor = mul(factor, partial).ok_or(FixedError::Calculation("compound overflow"))?;
+    }
+    let inner = mul(principal.inner, factor).ok_or(FixedError::Calculation("compound overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the amount `principal * e^(rate * years)` under continuous compounding.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = continuousCompoundInterest))]
+pub fn continuous_compound_interest(
+    principal: &I64F64,
+    rate: &I64F64,
+    years: &I64F64,
+    round_up: bool,
+) -> Result<I64F64, FixedError> {
+    check_non_negative(principal.inner, "principal must not be negative")?;
+    check_non_negative(rate.inner, "rate must not be negative")?;
+    check_non_negative(years.inner, "years must not be negative")?;
+    let mul: fn(_, _) -> _ = if round_up {
+        mul_up
+    } else {
+        types::I64F64::checked_mul
+    };
+    let exponent =
+        mul(rate.inner, years.inner).ok_or(FixedError::Calculation("exponent overflow"))?;
+    let factor = transcendental::exp_reduced(exponent)
+        .ok_or(FixedError::Calculation("exp calculation failed"))?;
+    let inner = mul(principal.inner, factor).ok_or(FixedError::Calculation("compound overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Convert a nominal annual rate compounded `periods_per_year` times into the effective annual
+/// yield, `(1 + apr / periods_per_year)^periods_per_year - 1`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = aprToApy))]
+pub fn apr_to_apy(apr: &I64F64, periods_per_year: u32) -> Result<I64F64, FixedError> {
+    let base = periodic_factor(apr.inner, periods_per_year, false)?;
+    let factor = powu(base, periods_per_year.into(), false)
+        .ok_or(FixedError::Calculation("apy overflow"))?;
+    let inner = factor - types::I64F64::ONE;
+    Ok(I64F64 { inner })
+}
+
+/// Convert an effective annual yield into the nominal annual rate compounded
+/// `periods_per_year` times, `periods_per_year * ((1 + apy)^(1 / periods_per_year) - 1)`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = apyToApr))]
+pub fn apy_to_apr(apy: &I64F64, periods_per_year: u32) -> Result<I64F64, FixedError> {
+    let inner = per_period_rate_inner(apy.inner, periods_per_year)?
+        .checked_mul_int(periods_per_year.into())
+        .ok_or(FixedError::Calculation("apr overflow"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Derive the rate per block that compounds to `apy` over `blocks_per_year` blocks,
+/// `(1 + apy)^(1 / blocks_per_year) - 1`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = perBlockRate))]
+pub fn per_block_rate(apy: &I64F64, blocks_per_year: u32) -> Result<I64F64, FixedError> {
+    let inner = per_period_rate_inner(apy.inner, blocks_per_year)?;
+    Ok(I64F64 { inner })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+    use approx::assert_relative_eq;
+    use fixed::traits::LossyInto;
+
+    fn to_f64(v: &I64F64) -> f64 {
+        v.inner.lossy_into()
+    }
+
+    #[test]
+    fn compound_interest_works() {
+        let principal = num("1000");
+        let rate = num("0.05");
+
+        let result = compound_interest(&principal, &rate, 12, &num("10"), false).unwrap();
+        let expected = 1000.0 * (1.0 + 0.05_f64 / 12.0).powi(120);
+        assert_relative_eq!(to_f64(&result), expected, max_relative = 1.0e-14);
+
+        let down = compound_interest(&principal, &rate, 365, &num("2.5"), false).unwrap();
+        let up = compound_interest(&principal, &rate, 365, &num("2.5"), true).unwrap();
+        let expected = 1000.0 * (1.0 + 0.05_f64 / 365.0).powf(912.5);
+        assert_relative_eq!(to_f64(&down), expected, max_relative = 1.0e-12);
+        assert!(down.lt(&up));
+
+        let zero = num("0");
+        assert_eq!(
+            compound_interest(&principal, &rate, 12, &zero, false).unwrap(),
+            principal
+        );
+        assert!(compound_interest(&principal, &rate, 0, &num("1"), false).is_err());
+        assert!(compound_interest(&principal, &num("-0.1"), 12, &num("1"), false).is_err());
+        assert!(compound_interest(&principal, &num("100"), 1, &num("100"), false).is_err());
+    }
+
+    #[test]
+    fn compound_interest_per_block() {
+        // about one block every 8 seconds
+        let blocks = 3_942_000;
+        let result = compound_interest(&num("1"), &num("0.1"), blocks, &num("1"), false).unwrap();
+        assert_relative_eq!(to_f64(&result), 0.1_f64.exp(), max_relative = 1.0e-7);
+    }
+
+    #[test]
+    fn continuous_compound_interest_works() {
+        let result =
+            continuous_compound_interest(&num("1000"), &num("0.05"), &num("3"), false).unwrap();
+        assert_relative_eq!(
+            to_f64(&result),
+            1000.0 * 0.15_f64.exp(),
+            max_relative = 1.0e-14
+        );
+        let up = continuous_compound_interest(&num("1000"), &num("0.05"), &num("3"), true).unwrap();
+        assert!(result.le(&up));
+        assert!(continuous_compound_interest(&num("1"), &num("1000"), &num("1"), false).is_err());
+
+        // e^30, far outside the range where a plain Taylor series stays precise
+        let large =
+            continuous_compound_interest(&num("1"), &num("0.5"), &num("60"), false).unwrap();
+        assert_relative_eq!(to_f64(&large), 30.0_f64.exp(), max_relative = 1.0e-14);
+    }
+
+    #[test]
+    fn rate_conversion_works() {
+        let apr = num("0.12");
+        let apy = apr_to_apy(&apr, 12).unwrap();
+        assert_relative_eq!(
+            to_f64(&apy),
+            1.01_f64.powi(12) - 1.0,
+            max_relative = 1.0e-14
+        );
+
+        let back = apy_to_apr(&apy, 12).unwrap();
+        assert_relative_eq!(to_f64(&back), 0.12, max_relative = 1.0e-14);
+
+        let rate = per_block_rate(&num("0.1"), 3_942_000).unwrap();
+        let expected = (1.1_f64.ln() / 3_942_000.0).exp_m1();
+        assert_relative_eq!(to_f64(&rate), expected, max_relative = 1.0e-9);
+
+        assert_eq!(per_block_rate(&num("0"), 100).unwrap(), num("0"));
+        assert!(per_block_rate(&num("0.1"), 0).is_err());
+        assert!(apy_to_apr(&num("-0.1"), 12).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+//! Financial math for lending, payment and derivatives protocols.
+//!
+//! Like [`crate::amm`], these functions operate on the `I64F64` wrapper and are
+//! exported through wasm-bindgen under the same names in camel case.
+pub mod annuity;
+pub mod interest;
+pub mod options;
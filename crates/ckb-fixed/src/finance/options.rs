@@ -0,0 +1,365 @@
+//! Black-Scholes pricing of European options.
+//!
+//! `rate` is the continuously compounded risk-free rate and `volatility` the
+//! annualized standard deviation of log returns, both as fractions, with `time`
+//! to expiry in years. The underlying pays no dividends. Theta is per year and
+//! vega per unit of volatility, so divide them by 365 and 100 for the usual
+//! per-day and per-point figures.
+//!
+//! The normal distribution comes from [`transcendental::normal_cdf`], so a
+//! price computed in a script matches the one quoted in the browser bit for bit.
+use crate::{transcendental, types, FixedError, I64F64};
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+/// The upper bound of the implied volatility search, 1000%.
+const MAX_VOLATILITY: types::I64F64 = types::I64F64::lit("10");
+/// Implied volatility searches stop once a step moves the volatility by less than this.
+const VOLATILITY_TOLERANCE: types::I64F64 = types::I64F64::lit("0.000000000001");
+const MAX_ITERATIONS: usize = 100;
+
+fn overflow() -> FixedError {
+    FixedError::Calculation("option pricing overflow")
+}
+
+fn check_positive(value: types::I64F64, msg: &'static str) -> Result<(), FixedError> {
+    if value <= types::I64F64::ZERO {
+        return Err(FixedError::Calculation(msg));
+    }
+    Ok(())
+}
+
+/// The right to buy (call) or sell (put) the underlying at the strike price.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+/// A European option priced with the Black-Scholes model.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlackScholes {
+    spot: types::I64F64,
+    strike: types::I64F64,
+    rate: types::I64F64,
+    volatility: types::I64F64,
+    sqrt_time: types::I64F64,
+    /// `e^(-rate * time)`
+    discount: types::I64F64,
+    d1: types::I64F64,
+    d2: types::I64F64,
+}
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl BlackScholes {
+    /// Set up the model for an option on `spot` struck at `strike` and expiring in `time` years.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(constructor))]
+    pub fn new(
+        spot: &I64F64,
+        strike: &I64F64,
+        rate: &I64F64,
+        volatility: &I64F64,
+        time: &I64F64,
+    ) -> Result<BlackScholes, FixedError> {
+        BlackScholes::from_inner(
+            spot.inner,
+            strike.inner,
+            rate.inner,
+            volatility.inner,
+            time.inner,
+        )
+    }
+    /// The premium of the option.
+    pub fn price(&self, kind: OptionKind) -> Result<I64F64, FixedError> {
+        let inner = self.price_inner(kind)?;
+        Ok(I64F64 { inner })
+    }
+    /// The sensitivity of the premium to the spot price.
+    pub fn delta(&self, kind: OptionKind) -> I64F64 {
+        let inner = match kind {
+            OptionKind::Call => transcendental::normal_cdf(self.d1),
+            OptionKind::Put => -transcendental::normal_cdf(-self.d1),
+        };
+        I64F64 { inner }
+    }
+    /// The sensitivity of delta to the spot price, the same for calls and puts.
+    pub fn gamma(&self) -> Result<I64F64, FixedError> {
+        let inner = self
+            .spot
+            .checked_mul(self.volatility)
+            .and_then(|r| r.checked_mul(self.sqrt_time))
+            .and_then(|r| transcendental::normal_pdf(self.d1).checked_div(r))
+            .ok_or_else(overflow)?;
+        Ok(I64F64 { inner })
+    }
+    /// The sensitivity of the premium to the volatility, the same for calls and puts.
+    pub fn vega(&self) -> Result<I64F64, FixedError> {
+        let inner = self.vega_inner()?;
+        Ok(I64F64 { inner })
+    }
+    /// The sensitivity of the premium to the passage of time, per year.
+    pub fn theta(&self, kind: OptionKind) -> Result<I64F64, FixedError> {
+        // spot * pdf(d1) * volatility / (2 * sqrt(time))
+        let decay = self
+            .spot
+            .checked_mul(transcendental::normal_pdf(self.d1))
+            .and_then(|r| r.checked_mul(self.volatility))
+            .and_then(|r| r.checked_div(self.sqrt_time * 2))
+            .ok_or_else(overflow)?;
+        let carry = self
+            .strike
+            .checked_mul(self.discount)
+            .and_then(|r| r.checked_mul(self.rate))
+            .ok_or_else(overflow)?;
+        let inner = match kind {
+            OptionKind::Call => -decay - carry * transcendental::normal_cdf(self.d2),
+            OptionKind::Put => -decay + carry * transcendental::normal_cdf(-self.d2),
+        };
+        Ok(I64F64 { inner })
+    }
+}
+
+impl BlackScholes {
+    fn from_inner(
+        spot: types::I64F64,
+        strike: types::I64F64,
+        rate: types::I64F64,
+        volatility: types::I64F64,
+        time: types::I64F64,
+    ) -> Result<BlackScholes, FixedError> {
+        check_positive(spot, "spot must be positive")?;
+        check_positive(strike, "strike must be positive")?;
+        check_positive(volatility, "volatility must be positive")?;
+        check_positive(time, "time must be positive")?;
+        let sqrt_time: types::I64F64 = transcendental::sqrt(time)
+            .map_err(|_| FixedError::Calculation("sqrt calculation failed"))?;
+        let deviation = volatility.checked_mul(sqrt_time).ok_or_else(overflow)?;
+        if deviation == types::I64F64::ZERO {
+            return Err(overflow());
+        }
+        let moneyness = transcendental::ln_normalized(spot)
+            .zip(transcendental::ln_normalized(strike))
+            .map(|(s, k)| s - k)
+            .ok_or(FixedError::Calculation("ln calculation failed"))?;
+        // (ln(spot / strike) + (rate + volatility^2 / 2) * time) / deviation
+        let d1 = volatility
+            .checked_mul(volatility)
+            .and_then(|v| (v / 2).checked_add(rate))
+            .and_then(|r| r.checked_mul(time))
+            .and_then(|r| r.checked_add(moneyness))
+            .and_then(|r| r.checked_div(deviation))
+            .ok_or_else(overflow)?;
+        let d2 = d1.checked_sub(deviation).ok_or_else(overflow)?;
+        let discount = rate
+            .checked_mul(time)
+            .and_then(|r| transcendental::exp_reduced(-r))
+            .ok_or_else(overflow)?;
+        Ok(BlackScholes {
+            spot,
+            strike,
+            rate,
+            volatility,
+            sqrt_time,
+            discount,
+            d1,
+            d2,
+        })
+    }
+
+    fn price_inner(&self, kind: OptionKind) -> Result<types::I64F64, FixedError> {
+        let (d1, d2, sign) = match kind {
+            OptionKind::Call => (self.d1, self.d2, types::I64F64::ONE),
+            OptionKind::Put => (-self.d1, -self.d2, -types::I64F64::ONE),
+        };
+        // call: spot * N(d1) - strike * discount * N(d2)
+        // put:  strike * discount * N(-d2) - spot * N(-d1)
+        let asset = self.spot.checked_mul(transcendental::normal_cdf(d1));
+        let cash = self
+            .strike
+            .checked_mul(self.discount)
+            .and_then(|r| r.checked_mul(transcendental::normal_cdf(d2)));
+        asset
+            .zip(cash)
+            .and_then(|(a, c)| a.checked_sub(c))
+            .map(|r| (r * sign).max(types::I64F64::ZERO))
+            .ok_or_else(overflow)
+    }
+
+    fn vega_inner(&self) -> Result<types::I64F64, FixedError> {
+        self.spot
+            .checked_mul(transcendental::normal_pdf(self.d1))
+            .and_then(|r| r.checked_mul(self.sqrt_time))
+            .ok_or_else(overflow)
+    }
+}
+
+/// Find the volatility at which the option is worth `price`.
+///
+/// Newton's method on vega converges in a few steps from the usual starting
+/// point; a bracket around the root is kept and any step leaving it falls back
+/// to bisection, so the search also terminates for deep in- or out-of-the-money
+/// options where vega vanishes. Fails when `price` is outside the no-arbitrage
+/// bounds or implies a volatility above 1000%.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = impliedVolatility))]
+pub fn implied_volatility(
+    kind: OptionKind,
+    price: &I64F64,
+    spot: &I64F64,
+    strike: &I64F64,
+    rate: &I64F64,
+    time: &I64F64,
+) -> Result<I64F64, FixedError> {
+    let (target, spot, strike, rate, time) = (
+        price.inner,
+        spot.inner,
+        strike.inner,
+        rate.inner,
+        time.inner,
+    );
+    let model = |volatility| BlackScholes::from_inner(spot, strike, rate, volatility, time);
+
+    let upper = model(MAX_VOLATILITY)?.price_inner(kind)?;
+    if target >= upper {
+        return Err(FixedError::Calculation("price above the volatility bound"));
+    }
+    let lower = model(VOLATILITY_TOLERANCE)?.price_inner(kind)?;
+    if target <= lower {
+        return Err(FixedError::Calculation("price below the intrinsic value"));
+    }
+
+    let (mut low, mut high) = (VOLATILITY_TOLERANCE, MAX_VOLATILITY);
+    // Brenner-Subrahmanyam, exact for at-the-money options: price / spot * sqrt(2 * pi / time)
+    let mut volatility = target
+        .checked_div(spot)
+        .zip(transcendental::sqrt::<_, types::I64F64>(types::I64F64::TAU / time).ok())
+        .and_then(|(a, b)| a.checked_mul(b))
+        .filter(|v| *v > low && *v < high)
+        .unwrap_or(types::I64F64::ONE / 2);
+    for _ in 0..MAX_ITERATIONS {
+        let model = model(volatility)?;
+        let diff = model.price_inner(kind)? - target;
+        if diff == types::I64F64::ZERO {
+            break;
+        }
+        if diff > types::I64F64::ZERO {
+            high = volatility;
+        } else {
+            low = volatility;
+        }
+        let next = model
+            .vega_inner()
+            .ok()
+            .and_then(|vega| diff.checked_div(vega))
+            .map(|step| volatility - step)
+            .filter(|v| *v > low && *v < high)
+            .unwrap_or_else(|| low.mean(high));
+        let step = next.dist(volatility);
+        volatility = next;
+        if step < VOLATILITY_TOLERANCE || high - low < VOLATILITY_TOLERANCE {
+            break;
+        }
+    }
+    Ok(I64F64 { inner: volatility })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+    use approx::assert_relative_eq;
+    use fixed::traits::LossyInto;
+
+    fn to_f64(v: &I64F64) -> f64 {
+        v.inner.lossy_into()
+    }
+
+    fn model(spot: &str, strike: &str, rate: &str, volatility: &str, time: &str) -> BlackScholes {
+        BlackScholes::new(
+            &num(spot),
+            &num(strike),
+            &num(rate),
+            &num(volatility),
+            &num(time),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn price_and_greeks_works() {
+        let m = model("100", "100", "0.05", "0.2", "1");
+        let cases = [
+            (m.price(OptionKind::Call).unwrap(), 10.450583572185565),
+            (m.price(OptionKind::Put).unwrap(), 5.573526022256971),
+            (m.delta(OptionKind::Call), 0.6368306511756191),
+            (m.delta(OptionKind::Put), -0.3631693488243809),
+            (m.gamma().unwrap(), 0.018762017345846895),
+            (m.vega().unwrap(), 37.52403469169379),
+            (m.theta(OptionKind::Call).unwrap(), -6.414027546438197),
+            (m.theta(OptionKind::Put).unwrap(), -1.657880423934626),
+        ];
+        for (result, expected) in cases {
+            assert_relative_eq!(to_f64(&result), expected, max_relative = 1.0e-12);
+        }
+
+        // Hull, Options, Futures and Other Derivatives, example 15.6
+        let m = model("42", "40", "0.1", "0.2", "0.5");
+        let call = m.price(OptionKind::Call).unwrap();
+        assert_relative_eq!(to_f64(&call), 4.759422392871535, max_relative = 1.0e-12);
+        let put = m.price(OptionKind::Put).unwrap();
+        assert_relative_eq!(to_f64(&put), 0.8085993729000958, max_relative = 1.0e-12);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let (one, zero) = (num("1"), num("0"));
+        for (spot, strike, volatility, time) in [
+            (&zero, &one, &one, &one),
+            (&one, &zero, &one, &one),
+            (&one, &one, &zero, &one),
+            (&one, &one, &one, &zero),
+        ] {
+            assert!(BlackScholes::new(spot, strike, &one, volatility, time).is_err());
+        }
+    }
+
+    #[test]
+    fn implied_volatility_works() {
+        let cases = [
+            ("100", "100", "0.05", "0.2", "1"),
+            ("42", "40", "0.1", "0.2", "0.5"),
+            ("3000", "4500", "0.03", "0.75", "0.0833"),
+            ("3000", "1500", "0.03", "0.4", "2"),
+            ("1", "1.05", "0", "3.5", "0.25"),
+        ];
+        for (spot, strike, rate, volatility, time) in cases {
+            let m = model(spot, strike, rate, volatility, time);
+            for kind in [OptionKind::Call, OptionKind::Put] {
+                let price = m.price(kind).unwrap();
+                let result = implied_volatility(
+                    kind,
+                    &price,
+                    &num(spot),
+                    &num(strike),
+                    &num(rate),
+                    &num(time),
+                )
+                .unwrap();
+                assert_relative_eq!(
+                    to_f64(&result),
+                    to_f64(&num(volatility)),
+                    max_relative = 1.0e-9
+                );
+            }
+        }
+
+        let (spot, strike, rate, time) = (num("100"), num("100"), num("0.05"), num("1"));
+        let iv =
+            |kind, price: &str| implied_volatility(kind, &num(price), &spot, &strike, &rate, &time);
+        // below the discounted intrinsic value and above the spot price
+        assert!(iv(OptionKind::Call, "4").is_err());
+        assert!(iv(OptionKind::Call, "100").is_err());
+        assert!(iv(OptionKind::Put, "0").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! Linear and piecewise-linear interpolation.
+//!
+//! A [`PiecewiseLinear`] function is defined by breakpoints `(x, y)` with
+//! strictly increasing `x`, such as the kinked utilization curve of an
+//! interest-rate model. Inputs outside the breakpoints evaluate to the value at
+//! the nearest end, so the function is flat beyond its first and last breakpoint.
+//!
+//! A function serializes to cell data as its breakpoints in order, each as 32
+//! bytes:
+//!
+//! | offset | size | field            |
+//! |--------|------|------------------|
+//! | 0      | 16   | x, little endian |
+//! | 16     | 16   | y, little endian |
+use crate::{types, FixedError, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+/// The size of one encoded breakpoint.
+pub const BREAKPOINT_LEN: usize = 32;
+
+fn overflow() -> FixedError {
+    FixedError::Calculation("interpolation overflow")
+}
+
+fn lerp_inner(
+    a: types::I64F64,
+    b: types::I64F64,
+    t: types::I64F64,
+) -> Result<types::I64F64, FixedError> {
+    b.checked_sub(a)
+        .and_then(|d| d.checked_mul(t))
+        .and_then(|d| d.checked_add(a))
+        .ok_or_else(overflow)
+}
+
+fn inverse_lerp_inner(
+    a: types::I64F64,
+    b: types::I64F64,
+    value: types::I64F64,
+) -> Result<types::I64F64, FixedError> {
+    if a == b {
+        return Err(FixedError::Calculation(
+            "interpolation range must not be empty",
+        ));
+    }
+    let span = b.checked_sub(a).ok_or_else(overflow)?;
+    value
+        .checked_sub(a)
+        .and_then(|d| d.checked_div(span))
+        .ok_or_else(overflow)
+}
+
+/// Interpolate linearly from `a` at `t = 0` to `b` at `t = 1`, `a + (b - a) * t`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+pub fn lerp(a: &I64F64, b: &I64F64, t: &I64F64) -> Result<I64F64, FixedError> {
+    let inner = lerp_inner(a.inner, b.inner, t.inner)?;
+    Ok(I64F64 { inner })
+}
+
+/// Find the `t` at which `lerp(a, b, t)` is `value`, `(value - a) / (b - a)`.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = inverseLerp))]
+pub fn inverse_lerp(a: &I64F64, b: &I64F64, value: &I64F64) -> Result<I64F64, FixedError> {
+    let inner = inverse_lerp_inner(a.inner, b.inner, value.inner)?;
+    Ok(I64F64 { inner })
+}
+
+/// Piecewise-linear function through sorted breakpoints.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiecewiseLinear {
+    points: Vec<(types::I64F64, types::I64F64)>,
+}
+
+#[cfg(feature = "alloc")]
+impl PiecewiseLinear {
+    /// Create a function through `points`, which need at least two breakpoints with strictly
+    /// increasing `x`.
+    pub fn new(points: &[(I64F64, I64F64)]) -> Result<PiecewiseLinear, FixedError> {
+        let points = points.iter().map(|(x, y)| (x.inner, y.inner)).collect();
+        PiecewiseLinear::from_inner(points)
+    }
+
+    fn from_inner(
+        points: Vec<(types::I64F64, types::I64F64)>,
+    ) -> Result<PiecewiseLinear, FixedError> {
+        if points.len() < 2 {
+            return Err(FixedError::Calculation(
+                "at least two breakpoints are required",
+            ));
+        }
+        if points.windows(2).any(|w| w[0].0 >= w[1].0) {
+            return Err(FixedError::Calculation(
+                "breakpoints must be strictly increasing",
+            ));
+        }
+        Ok(PiecewiseLinear { points })
+    }
+
+    /// The breakpoints of the function.
+    pub fn points(&self) -> impl Iterator<Item = (I64F64, I64F64)> + '_ {
+        self.points.iter().map(|(x, y)| ((*x).into(), (*y).into()))
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl PiecewiseLinear {
+    /// Evaluate the function at `x`.
+    pub fn evaluate(&self, x: &I64F64) -> Result<I64F64, FixedError> {
+        let x = x.inner;
+        let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+        let inner = if x <= first.0 {
+            first.1
+        } else if x >= last.0 {
+            last.1
+        } else {
+            // the first breakpoint beyond `x`, which is neither the first nor past the last
+            let i = self.points.partition_point(|(px, _)| *px <= x);
+            let ((x0, y0), (x1, y1)) = (self.points[i - 1], self.points[i]);
+            let t = inverse_lerp_inner(x0, x1, x)?;
+            lerp_inner(y0, y1, t)?
+        };
+        Ok(I64F64 { inner })
+    }
+    /// Whether the function never decreases.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = isNonDecreasing))]
+    pub fn is_non_decreasing(&self) -> bool {
+        self.points.windows(2).all(|w| w[0].1 <= w[1].1)
+    }
+    /// Whether the function never increases.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = isNonIncreasing))]
+    pub fn is_non_increasing(&self) -> bool {
+        self.points.windows(2).all(|w| w[0].1 >= w[1].1)
+    }
+    /// Serialize the function to bytes for cell data.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toBytes))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.points.len() * BREAKPOINT_LEN);
+        for (x, y) in &self.points {
+            bytes.extend_from_slice(&x.to_le_bytes());
+            bytes.extend_from_slice(&y.to_le_bytes());
+        }
+        bytes
+    }
+    /// Deserialize a function from bytes produced by `to_bytes`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromBytes))]
+    pub fn from_bytes(bytes: &[u8]) -> Result<PiecewiseLinear, FixedError> {
+        if bytes.len() % BREAKPOINT_LEN != 0 {
+            return Err(FixedError::InvalidLength);
+        }
+        let points = bytes
+            .chunks_exact(BREAKPOINT_LEN)
+            .map(|chunk| {
+                let x = I64F64::from_le_bytes(&chunk[..16])?.inner;
+                let y = I64F64::from_le_bytes(&chunk[16..])?.inner;
+                Ok((x, y))
+            })
+            .collect::<Result<_, FixedError>>()?;
+        PiecewiseLinear::from_inner(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+
+    /// Borrow rate kinked at 75% utilization.
+    fn kinked() -> PiecewiseLinear {
+        PiecewiseLinear::new(&[
+            (num("0"), num("0.0625")),
+            (num("0.75"), num("0.125")),
+            (num("1"), num("1.125")),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn lerp_works() {
+        assert_eq!(
+            lerp(&num("2"), &num("4"), &num("0.25")).unwrap(),
+            num("2.5")
+        );
+        assert_eq!(
+            lerp(&num("4"), &num("2"), &num("0.25")).unwrap(),
+            num("3.5")
+        );
+        assert_eq!(lerp(&num("2"), &num("4"), &num("2")).unwrap(), num("6"));
+        assert_eq!(
+            inverse_lerp(&num("2"), &num("4"), &num("2.5")).unwrap(),
+            num("0.25")
+        );
+        assert_eq!(
+            inverse_lerp(&num("4"), &num("2"), &num("3.5")).unwrap(),
+            num("0.25")
+        );
+        assert!(inverse_lerp(&num("2"), &num("2"), &num("2")).is_err());
+        let max = I64F64::from(types::I64F64::MAX);
+        let min = I64F64::from(types::I64F64::MIN);
+        assert!(lerp(&min, &max, &num("0.5")).is_err());
+    }
+
+    #[test]
+    fn evaluate_works() {
+        let curve = kinked();
+        assert_eq!(curve.evaluate(&num("0")).unwrap(), num("0.0625"));
+        assert_eq!(curve.evaluate(&num("0.375")).unwrap(), num("0.09375"));
+        assert_eq!(curve.evaluate(&num("0.75")).unwrap(), num("0.125"));
+        assert_eq!(curve.evaluate(&num("0.875")).unwrap(), num("0.625"));
+        assert_eq!(curve.evaluate(&num("1")).unwrap(), num("1.125"));
+        // flat beyond the breakpoints
+        assert_eq!(curve.evaluate(&num("-1")).unwrap(), num("0.0625"));
+        assert_eq!(curve.evaluate(&num("1.5")).unwrap(), num("1.125"));
+    }
+
+    #[test]
+    fn monotonicity() {
+        let curve = kinked();
+        assert!(curve.is_non_decreasing());
+        assert!(!curve.is_non_increasing());
+
+        let bonus = PiecewiseLinear::new(&[
+            (num("1"), num("0.1")),
+            (num("1.2"), num("0.05")),
+            (num("1.5"), num("0.05")),
+        ])
+        .unwrap();
+        assert!(!bonus.is_non_decreasing());
+        assert!(bonus.is_non_increasing());
+    }
+
+    #[test]
+    fn invalid_breakpoints() {
+        assert!(PiecewiseLinear::new(&[(num("0"), num("1"))]).is_err());
+        assert!(PiecewiseLinear::new(&[(num("0"), num("1")), (num("0"), num("2"))]).is_err());
+        assert!(PiecewiseLinear::new(&[(num("1"), num("1")), (num("0"), num("2"))]).is_err());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let curve = kinked();
+        let bytes = curve.to_bytes();
+        assert_eq!(bytes.len(), 3 * BREAKPOINT_LEN);
+        assert_eq!(&bytes[32..48], num("0.75").to_le_bytes().as_slice());
+        assert_eq!(&bytes[48..64], num("0.125").to_le_bytes().as_slice());
+        assert_eq!(PiecewiseLinear::from_bytes(&bytes).unwrap(), curve);
+        let points: Vec<_> = curve.points().collect();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[2], (num("1"), num("1.125")));
+
+        assert!(PiecewiseLinear::from_bytes(&bytes[1..]).is_err());
+        assert!(PiecewiseLinear::from_bytes(&bytes[..32]).is_err());
+        let mut unsorted = bytes[32..].to_vec();
+        unsorted.extend_from_slice(&bytes[..32]);
+        assert!(PiecewiseLinear::from_bytes(&unsorted).is_err());
+    }
+}
@@ -18,6 +18,32 @@
 //! - Basic mathematical operations (+, -, *, /, %)
 //! - Implements common traits like `Add`, `Sub`, `Mul`, `Div`
 //! - Transcendental functions like `exp`, `ln`, `pow`, `log2`, `sin`, etc.
+//! - Cube and n-th roots that take negative operands for odd degrees
+//! - `hypot` and Euclidean norms in [`stats::norm2`] that never overflow on the squares
+//! - `log10`, `log_b`, `exp2`, and the near-zero precise `expm1` and `ln1p`
+//! - Gaussian functions `erf`, `erfc` and the normal PDF, CDF and inverse CDF
+//! - Concentrated-liquidity tick math in [`amm::clmm`]
+//! - Interest and compounding in [`finance::interest`], loan amortization in [`finance::annuity`]
+//! - Black-Scholes option prices, greeks and implied volatility in [`finance::options`]
+//! - Bonding curves with closed-form integrals in [`curves`]
+//! - Time-weighted average price accumulator in [`oracle::twap`]
+//! - Median, weighted median, trimmed mean and outlier rejection for oracle feeds in
+//!   [`oracle::aggregate`]
+//! - Linear and piecewise-linear interpolation in [`interp`]
+//! - Polynomial evaluation in [`poly`], with a least-squares fitter under `std`
+//! - Welford mean, variance, covariance and correlation in [`stats`]
+//! - Molecule readers and builders for cell data in `mol`, under the `molecule` feature
+//! - Serde support as exact decimal strings, with hex and raw-bits adapters, under the
+//!   `serde` feature
+//! - Borsh support as the 16 little-endian bytes under the `borsh` feature, and a
+//!   compact variable-length encoding for small values in [`compact`]
+//! - Canonical fixed-width decimal strings for human-auditable cell data in [`decimal`]
+//! - Builds without the `alloc` crate when the `alloc` default feature is off, converting
+//!   with `to_le_array`, `write_le_bytes` and `from_le_array`
+//! - Element-wise batch operations and formulas over packed arrays in [`batch`], bit-identical
+//!   to the scalar API
+//! - JavaScript `bigint` interop for raw bits, integers and integer and fractional parts
+//! - Approximate `f64` conversions for off-chain analytics under `std`, never on-chain
 //! - No floating-point dependencies
 //!
 //! # Example
@@ -28,9 +54,33 @@
 //! let a = I64F64::from_num(5).unwrap();
 //! let result = a.ln().unwrap();
 //! ```
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
+pub mod amm;
+pub mod batch;
+pub mod compact;
+pub mod curves;
+pub mod decimal;
+pub mod finance;
+pub mod interp;
+mod math;
+#[cfg(feature = "molecule")]
+pub mod mol;
+pub mod oracle;
+#[cfg(feature = "alloc")]
+mod packed;
+pub mod poly;
+mod rounding;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod stats;
+#[cfg(test)]
+mod test_util;
 pub mod transcendental;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 pub use fixed::types;
 #[cfg(feature = "wasm-bindgen")]
@@ -43,20 +93,47 @@ pub enum FixedError {
     Calculation(&'static str),
 }
 
+impl FixedError {
+    /// A stable code for the kind of error: `INVALID_LENGTH`, `INVALID_NUMBER` or `CALCULATION`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FixedError::InvalidLength => "INVALID_LENGTH",
+            FixedError::InvalidNumber => "INVALID_NUMBER",
+            FixedError::Calculation(_) => "CALCULATION",
+        }
+    }
+    /// A human-readable description of the error.
+    pub fn message(&self) -> &'static str {
+        match self {
+            FixedError::InvalidLength => "Invalid length",
+            FixedError::InvalidNumber => "Invalid number",
+            FixedError::Calculation(msg) => msg,
+        }
+    }
+}
+
+/// Thrown to JavaScript as the string of [`FixedError::message`], see [`error_code`].
 #[cfg(feature = "wasm-bindgen")]
 impl From<FixedError> for JsValue {
     fn from(error: FixedError) -> JsValue {
-        match error {
-            FixedError::InvalidLength => JsValue::from_str("Invalid length"),
-            FixedError::InvalidNumber => JsValue::from_str("Invalid number"),
-            FixedError::Calculation(msg) => JsValue::from_str(msg),
-        }
+        JsValue::from_str(error.message())
     }
 }
 
+/// The [`FixedError::code`] of an error thrown by the bindings, from its message.
+#[cfg(all(feature = "wasm-bindgen", feature = "alloc"))]
+#[wasm_bindgen(js_name = errorCode)]
+pub fn error_code(message: &str) -> String {
+    let error = [FixedError::InvalidLength, FixedError::InvalidNumber]
+        .into_iter()
+        .find(|error| error.message() == message)
+        .unwrap_or(FixedError::Calculation(""));
+    String::from(error.code())
+}
+
 /// The fixed-point default number type, 64-bit integer with 64 fractional bits
 #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct I64F64 {
     inner: types::I64F64,
 }
@@ -73,6 +150,21 @@ impl From<types::I64F64> for I64F64 {
     }
 }
 
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for I64F64 {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        writer.write_all(&self.inner.to_le_bytes())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for I64F64 {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let bytes = <[u8; 16]>::deserialize_reader(reader)?;
+        Ok(types::I64F64::from_le_bytes(bytes).into())
+    }
+}
+
 // bindings to fixed crate
 #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
 impl I64F64 {
@@ -108,6 +200,7 @@ impl I64F64 {
         Ok(I64F64 { inner })
     }
     /// Convert the `I64F64` to a byte slice.
+    #[cfg(feature = "alloc")]
     #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toLeBytes))]
     pub fn to_le_bytes(&self) -> Vec<u8> {
         self.inner.to_le_bytes().to_vec()
@@ -119,6 +212,86 @@ impl I64F64 {
             types::I64F64::from_le_bytes(bytes.try_into().map_err(|_| FixedError::InvalidLength)?);
         Ok(I64F64 { inner })
     }
+    /// Convert the `I64F64` to a byte slice in big endian.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toBeBytes))]
+    pub fn to_be_bytes(&self) -> Vec<u8> {
+        self.inner.to_be_bytes().to_vec()
+    }
+    /// Create a new `I64F64` from its representation as a byte array in big endian.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromBeBytes))]
+    pub fn from_be_bytes(bytes: &[u8]) -> Result<I64F64, FixedError> {
+        let inner =
+            types::I64F64::from_be_bytes(bytes.try_into().map_err(|_| FixedError::InvalidLength)?);
+        Ok(I64F64 { inner })
+    }
+    /// Convert the `I64F64` to `0x` followed by the lowercase hex of its 16 little-endian bytes,
+    /// the way CKB tooling prints cell data.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toHex))]
+    pub fn to_hex(&self) -> String {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut hex = String::with_capacity(34);
+        hex.push_str("0x");
+        for byte in self.inner.to_le_bytes() {
+            hex.push(char::from(DIGITS[usize::from(byte >> 4)]));
+            hex.push(char::from(DIGITS[usize::from(byte & 0xf)]));
+        }
+        hex
+    }
+    /// Create a new `I64F64` from `0x` followed by the hex of its 16 little-endian bytes.
+    /// A missing prefix or a non-hex digit is `InvalidNumber`, any other digit count is
+    /// `InvalidLength`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromHex))]
+    pub fn from_hex(s: &str) -> Result<I64F64, FixedError> {
+        let digits = s
+            .strip_prefix("0x")
+            .ok_or(FixedError::InvalidNumber)?
+            .as_bytes();
+        if digits.len() != 32 {
+            return Err(FixedError::InvalidLength);
+        }
+        let digit = |c: u8| char::from(c).to_digit(16).ok_or(FixedError::InvalidNumber);
+        let mut bytes = [0u8; 16];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks_exact(2)) {
+            *byte = (digit(pair[0])? << 4 | digit(pair[1])?) as u8;
+        }
+        let inner = types::I64F64::from_le_bytes(bytes);
+        Ok(I64F64 { inner })
+    }
+    /// Convert the `I64F64` to its canonical decimal string with exactly `places` fractional
+    /// digits, see [`decimal`].
+    ///
+    /// Fails like [`decimal::encode`], including with `Calculation("decimal overflow")` for
+    /// values within half a unit in the last place of `MAX`, such as `MAX` with 2 places.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toCanonicalDecimal))]
+    pub fn to_canonical_decimal(&self, places: u32) -> Result<String, FixedError> {
+        let mut buf = [0u8; decimal::MAX_LEN];
+        Ok(decimal::encode(self, places, &mut buf)?.into())
+    }
+    /// Create a new `I64F64` from its canonical decimal string with `places` fractional digits,
+    /// rejecting any other spelling of the value.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromCanonicalDecimal))]
+    pub fn from_canonical_decimal(s: &str, places: u32) -> Result<I64F64, FixedError> {
+        decimal::decode(s.as_bytes(), places)
+    }
+    /// Convert the `I64F64` to its compact variable-length encoding, see [`compact`].
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toCompactBytes))]
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(compact::MAX_ENCODED_LEN);
+        compact::encode(self, &mut bytes);
+        bytes
+    }
+    /// Create a new `I64F64` from its compact encoding, which must fill `bytes` exactly.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromCompactBytes))]
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<I64F64, FixedError> {
+        match compact::decode(bytes)? {
+            (value, len) if len == bytes.len() => Ok(value),
+            _ => Err(FixedError::InvalidLength),
+        }
+    }
     /// Add two `I64F64` numbers.
     pub fn add(&self, b: &I64F64) -> Result<I64F64, FixedError> {
         let a = self.inner;
@@ -155,6 +328,18 @@ impl I64F64 {
             .ok_or(FixedError::Calculation("division by zero or overflow"))?;
         Ok(I64F64 { inner })
     }
+    /// Add two `I64F64` numbers, wrapping around at the boundary of the type.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = wrappingAdd))]
+    pub fn wrapping_add(&self, b: &I64F64) -> I64F64 {
+        let inner = self.inner.wrapping_add(b.inner);
+        I64F64 { inner }
+    }
+    /// Subtract two `I64F64` numbers, wrapping around at the boundary of the type.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = wrappingSub))]
+    pub fn wrapping_sub(&self, b: &I64F64) -> I64F64 {
+        let inner = self.inner.wrapping_sub(b.inner);
+        I64F64 { inner }
+    }
     /// Rounds to the next integer towards −∞.
     pub fn floor(&self) -> I64F64 {
         let a = self.inner;
@@ -195,6 +380,149 @@ impl I64F64 {
     }
 }
 
+// allocation-free conversions for on-chain scripts
+impl I64F64 {
+    /// Convert the `I64F64` to its 16 little-endian bytes.
+    pub fn to_le_array(&self) -> [u8; 16] {
+        self.inner.to_le_bytes()
+    }
+    /// Write the 16 little-endian bytes of the `I64F64` to `out`, which must be 16 bytes long.
+    pub fn write_le_bytes(&self, out: &mut [u8]) -> Result<(), FixedError> {
+        let out: &mut [u8; 16] = out.try_into().map_err(|_| FixedError::InvalidLength)?;
+        *out = self.inner.to_le_bytes();
+        Ok(())
+    }
+    /// Create a new `I64F64` from its 16 little-endian bytes.
+    pub fn from_le_array(bytes: [u8; 16]) -> I64F64 {
+        let inner = types::I64F64::from_le_bytes(bytes);
+        I64F64 { inner }
+    }
+}
+
+/// How to round a value that falls between two representable results.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// To the nearest value, with ties rounded to even.
+    Nearest,
+    /// Towards −∞.
+    Floor,
+    /// Towards +∞.
+    Ceil,
+    /// Towards zero.
+    TowardZero,
+}
+
+// raw bits and integer parts, `bits = int_part * 2^64 + frac_part`
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl I64F64 {
+    /// The integer part, rounded towards −∞.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = intPart))]
+    pub fn int_part(&self) -> i64 {
+        (self.inner.to_bits() >> 64) as i64
+    }
+    /// The fractional part in units of `2^-64`, always non-negative.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fracPart))]
+    pub fn frac_part(&self) -> u64 {
+        self.inner.to_bits() as u64
+    }
+    /// Round the `I64F64` to an integer with `rounding`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toBigInt))]
+    pub fn to_int(&self, rounding: Rounding) -> Result<i64, FixedError> {
+        let a = self.inner;
+        let rounded = match rounding {
+            Rounding::Nearest => a.checked_round_ties_even(),
+            Rounding::Floor => Some(a.floor()),
+            Rounding::Ceil => a.checked_ceil(),
+            Rounding::TowardZero => Some(a.round_to_zero()),
+        }
+        .ok_or(FixedError::Calculation("integer overflow"))?;
+        Ok(I64F64 { inner: rounded }.int_part())
+    }
+}
+
+impl I64F64 {
+    /// Create a new `I64F64` from its raw bits, the value times `2^64`.
+    pub fn from_bits(bits: i128) -> I64F64 {
+        let inner = types::I64F64::from_bits(bits);
+        I64F64 { inner }
+    }
+    /// The raw bits of the `I64F64`, the value times `2^64`.
+    pub fn to_bits(&self) -> i128 {
+        self.inner.to_bits()
+    }
+    /// Create a new `I64F64` from an integer, which must be within the range of `i64`.
+    pub fn from_int(n: i128) -> Result<I64F64, FixedError> {
+        let inner = types::I64F64::checked_from_num(n).ok_or(FixedError::InvalidNumber)?;
+        Ok(I64F64 { inner })
+    }
+}
+
+#[cfg(feature = "wasm-bindgen")]
+#[wasm_bindgen]
+extern "C" {
+    /// A JavaScript `bigint`.
+    #[wasm_bindgen(typescript_type = "bigint")]
+    pub type BigInt;
+}
+
+// `bigint` interop beyond 64 bits, which `wasm-bindgen` can't pass as a plain argument
+#[cfg(feature = "wasm-bindgen")]
+#[wasm_bindgen]
+impl I64F64 {
+    /// Create a new `I64F64` from its raw bits, the value times `2^64`.
+    #[wasm_bindgen(js_name = fromBits)]
+    pub fn from_bits_bigint(bits: BigInt) -> Result<I64F64, FixedError> {
+        let bits = i128::try_from(JsValue::from(bits)).map_err(|_| FixedError::InvalidNumber)?;
+        Ok(I64F64::from_bits(bits))
+    }
+    /// The raw bits of the `I64F64`, the value times `2^64`.
+    #[wasm_bindgen(js_name = toBits)]
+    pub fn to_bits_bigint(&self) -> BigInt {
+        JsValue::from(self.to_bits()).unchecked_into()
+    }
+    /// Create a new `I64F64` from an integer, which must be within the range of `i64`.
+    #[wasm_bindgen(js_name = fromBigInt)]
+    pub fn from_bigint(n: BigInt) -> Result<I64F64, FixedError> {
+        let n = i128::try_from(JsValue::from(n)).map_err(|_| FixedError::InvalidNumber)?;
+        I64F64::from_int(n)
+    }
+}
+
+// float conversions for off-chain use only: floating point is not guaranteed to give the same
+// results on every platform, so these are never part of an on-chain build
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl I64F64 {
+    /// Convert the `I64F64` to the nearest `f64`, for display and analytics only.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toNumber))]
+    pub fn to_f64(&self) -> f64 {
+        self.inner.to_num()
+    }
+    /// Create a new `I64F64` from an `f64`, rounding the bits below `2^-64` with `rounding`.
+    /// NaN and infinities are `InvalidNumber`, finite values outside the range are errors.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromNumber))]
+    pub fn from_f64_checked(x: f64, rounding: Rounding) -> Result<I64F64, FixedError> {
+        if !x.is_finite() {
+            return Err(FixedError::InvalidNumber);
+        }
+        // scaling by a power of two is exact, and so is rounding to an integer
+        let scaled = x * 2f64.powi(64);
+        let bits = match rounding {
+            Rounding::Nearest => scaled.round_ties_even(),
+            Rounding::Floor => scaled.floor(),
+            Rounding::Ceil => scaled.ceil(),
+            Rounding::TowardZero => scaled.trunc(),
+        };
+        let limit = 2f64.powi(127);
+        if bits < -limit || bits >= limit {
+            return Err(FixedError::Calculation("f64 out of range"));
+        }
+        let inner = types::I64F64::from_bits(bits as i128);
+        Ok(I64F64 { inner })
+    }
+}
+
 // bindings to transcendental module
 #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
 impl I64F64 {
@@ -241,6 +569,24 @@ impl I64F64 {
             .map_err(|_| FixedError::Calculation("powi calculation failed"))?;
         Ok(Self { inner })
     }
+    /// Calculate the cube root of `I64F64` number, negative for negative numbers.
+    pub fn cbrt(&self) -> Result<Self, FixedError> {
+        let inner = transcendental::cbrt(self.inner)
+            .map_err(|_| FixedError::Calculation("cbrt calculation failed"))?;
+        Ok(Self { inner })
+    }
+    /// Calculate the n-th root of `I64F64` number, negative for negative numbers and odd `n`.
+    pub fn root(&self, n: u32) -> Result<Self, FixedError> {
+        let inner = transcendental::root(self.inner, n)
+            .map_err(|_| FixedError::Calculation("root calculation failed"))?;
+        Ok(Self { inner })
+    }
+    /// Calculate `sqrt(self^2 + other^2)` without overflowing on the squares.
+    pub fn hypot(&self, other: &I64F64) -> Result<Self, FixedError> {
+        let inner = transcendental::hypot(self.inner, other.inner)
+            .map_err(|_| FixedError::Calculation("hypot calculation failed"))?;
+        Ok(Self { inner })
+    }
     /// Calculate the sine of `I64F64` number.
     pub fn sin(&self) -> Result<Self, FixedError> {
         let a = self.inner;
@@ -262,4 +608,64 @@ impl I64F64 {
             .map_err(|_| FixedError::Calculation("tan calculation failed"))?;
         Ok(Self { inner })
     }
+    /// Calculate the error function of `I64F64` number.
+    pub fn erf(&self) -> Self {
+        let inner = transcendental::erf(self.inner);
+        Self { inner }
+    }
+    /// Calculate the complementary error function of `I64F64` number.
+    pub fn erfc(&self) -> Self {
+        let inner = transcendental::erfc(self.inner);
+        Self { inner }
+    }
+    /// Calculate the standard normal probability density at `I64F64` number.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = normalPdf))]
+    pub fn normal_pdf(&self) -> Self {
+        let inner = transcendental::normal_pdf(self.inner);
+        Self { inner }
+    }
+    /// Calculate the standard normal cumulative distribution at `I64F64` number.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = normalCdf))]
+    pub fn normal_cdf(&self) -> Self {
+        let inner = transcendental::normal_cdf(self.inner);
+        Self { inner }
+    }
+    /// Calculate the inverse standard normal cumulative distribution of an `I64F64` probability.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = normalInvCdf))]
+    pub fn normal_inv_cdf(&self) -> Result<Self, FixedError> {
+        let inner = transcendental::normal_inv_cdf(self.inner)
+            .map_err(|_| FixedError::Calculation("normal_inv_cdf calculation failed"))?;
+        Ok(Self { inner })
+    }
+    /// Calculate the base-10 logarithm of `I64F64` number.
+    pub fn log10(&self) -> Result<Self, FixedError> {
+        let inner = transcendental::log10(self.inner)
+            .map_err(|_| FixedError::Calculation("log10 calculation failed"))?;
+        Ok(Self { inner })
+    }
+    /// Calculate the logarithm of `I64F64` number to the given base.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = logB))]
+    pub fn log_b(&self, base: &I64F64) -> Result<Self, FixedError> {
+        let inner = transcendental::log_b(self.inner, base.inner)
+            .map_err(|_| FixedError::Calculation("log_b calculation failed"))?;
+        Ok(Self { inner })
+    }
+    /// Calculate 2 raised to the power of `I64F64` number.
+    pub fn exp2(&self) -> Result<Self, FixedError> {
+        let inner = transcendental::exp2(self.inner)
+            .map_err(|_| FixedError::Calculation("exp2 calculation failed"))?;
+        Ok(Self { inner })
+    }
+    /// Calculate `e^x - 1` of `I64F64` number, precisely for values near zero.
+    pub fn expm1(&self) -> Result<Self, FixedError> {
+        let inner = transcendental::expm1(self.inner)
+            .map_err(|_| FixedError::Calculation("expm1 calculation failed"))?;
+        Ok(Self { inner })
+    }
+    /// Calculate `ln(1 + x)` of `I64F64` number, precisely for values near zero.
+    pub fn ln1p(&self) -> Result<Self, FixedError> {
+        let inner = transcendental::ln1p(self.inner)
+            .map_err(|_| FixedError::Calculation("ln1p calculation failed"))?;
+        Ok(Self { inner })
+    }
 }
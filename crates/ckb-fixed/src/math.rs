@@ -0,0 +1,83 @@
+//! Math helpers shared by the protocol modules.
+//!
+//! These work on the raw `fixed` type and return `None` on any failure; callers
+//! map that to their own `FixedError`.
+use crate::rounding::mul_up;
+use crate::transcendental;
+use crate::types::I64F64;
+
+/// Power of a non-negative base with an unsigned integer exponent, by repeated squaring.
+///
+/// Unlike `transcendental::powi`, the cost is logarithmic in `exponent`, which
+/// matters for per-block or per-second compounding.
+pub(crate) fn powu(mut base: I64F64, mut exponent: u64, round_up: bool) -> Option<I64F64> {
+    let mul: fn(_, _) -> _ = if round_up {
+        mul_up
+    } else {
+        I64F64::checked_mul
+    };
+    let mut result = I64F64::ONE;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul(result, base)?;
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = mul(base, base)?;
+        }
+    }
+    Some(result)
+}
+
+/// Power of a positive base with a fractional exponent, `e^(log2(base) * exponent * ln(2))`.
+///
+/// `transcendental::pow` goes through `ln`, whose `LOG2_E` constant only has 23
+/// fractional bits; this keeps all 64 bits of the `log2` result.
+pub(crate) fn powf(base: I64F64, exponent: I64F64) -> Option<I64F64> {
+    let log2 = transcendental::log2_normalized(base)?;
+    let exponent = log2
+        .checked_mul(exponent)?
+        .checked_mul(transcendental::LN_2)?;
+    transcendental::exp(exponent).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn powu_works() {
+        let two = I64F64::from_num(2);
+        assert_eq!(powu(two, 0, false), Some(I64F64::ONE));
+        assert_eq!(powu(two, 10, false), Some(I64F64::from_num(1024)));
+        assert_eq!(powu(two, 62, false), Some(I64F64::from_num(1u64 << 62)));
+        assert_eq!(powu(two, 63, false), None);
+
+        let base = I64F64::from_num(1.1);
+        let down = powu(base, 7, false).unwrap();
+        let up = powu(base, 7, true).unwrap();
+        let slow: I64F64 = crate::transcendental::powi(base, 7).unwrap();
+        assert!(down <= slow && slow <= up);
+        assert!(up - down < I64F64::from_bits(16));
+    }
+
+    #[test]
+    fn powf_works() {
+        let result = powf(I64F64::from_num(2), I64F64::from_num(0.5)).unwrap();
+        let expected = I64F64::SQRT_2;
+        assert!(result.dist(expected) < I64F64::from_bits(1 << 8));
+
+        let result = powf(I64F64::from_num(1.12), I64F64::ONE / 12).unwrap();
+        let back = powu(result, 12, false).unwrap();
+        assert!(back.dist(I64F64::from_num(1.12)) < I64F64::from_bits(1 << 8));
+
+        // bases from one up are not shifted, so the result is bit-identical
+        // to taking `transcendental::log2` directly
+        for (base, exponent) in [(1.0, 0.5), (1.12, 1.0 / 12.0), (1.05, 30.0), (37.5, -0.75)] {
+            let (base, exponent) = (I64F64::from_num(base), I64F64::from_num(exponent));
+            let log2: I64F64 = transcendental::log2(base).unwrap();
+            let expected = transcendental::exp(log2 * exponent * transcendental::LN_2).unwrap();
+            assert_eq!(powf(base, exponent), Some(expected));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Molecule types for fixed-point values in cell data and witnesses.
+//!
+//! The readers and builders are generated by [Molecule] from
+//! `schemas/fixed.mol`; regenerate them with `make molecule`. An `I64F64` is
+//! encoded as the 16 bytes of [`crate::I64F64::to_le_bytes`], so a verified
+//! reader can hand out fields straight from cell data:
+//!
+//! ```ignore
+//! let pair = PricePairReader::from_slice(&data)?;
+//! let price = pair.price()?;
+//! ```
+//!
+//! [Molecule]: https://github.com/nervosnetwork/molecule
+#[allow(clippy::all)]
+mod generated;
+
+use crate::{types, FixedError};
+use alloc::vec::Vec;
+pub use generated::*;
+use molecule::bytes::Bytes;
+use molecule::prelude::*;
+pub use molecule::prelude::{Byte, ByteReader};
+
+impl From<I64F64Reader<'_>> for crate::I64F64 {
+    fn from(reader: I64F64Reader<'_>) -> Self {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(reader.as_slice());
+        types::I64F64::from_le_bytes(bytes).into()
+    }
+}
+
+impl From<I64F64> for crate::I64F64 {
+    fn from(value: I64F64) -> Self {
+        value.as_reader().into()
+    }
+}
+
+impl From<&crate::I64F64> for I64F64 {
+    fn from(value: &crate::I64F64) -> Self {
+        I64F64::new_unchecked(Bytes::from(value.to_le_bytes()))
+    }
+}
+
+impl From<I64F64OptReader<'_>> for Option<crate::I64F64> {
+    fn from(reader: I64F64OptReader<'_>) -> Self {
+        reader.to_opt().map(Into::into)
+    }
+}
+
+impl From<Option<&crate::I64F64>> for I64F64Opt {
+    fn from(value: Option<&crate::I64F64>) -> Self {
+        I64F64Opt::new_builder().set(value.map(Into::into)).build()
+    }
+}
+
+impl From<I64F64VecReader<'_>> for Vec<crate::I64F64> {
+    fn from(reader: I64F64VecReader<'_>) -> Self {
+        reader.iter().map(Into::into).collect()
+    }
+}
+
+impl From<&[crate::I64F64]> for I64F64Vec {
+    fn from(values: &[crate::I64F64]) -> Self {
+        I64F64Vec::new_builder()
+            .extend(values.iter().map(Into::into))
+            .build()
+    }
+}
+
+impl<'r> PricePairReader<'r> {
+    /// The price of the base asset in the quote asset, `quote / base`.
+    pub fn price(&self) -> Result<crate::I64F64, FixedError> {
+        let base = crate::I64F64::from(self.base());
+        let quote = crate::I64F64::from(self.quote());
+        quote
+            .inner
+            .checked_div(base.inner)
+            .map(Into::into)
+            .ok_or(FixedError::Calculation("price overflow"))
+    }
+}
+
+impl PricePair {
+    /// Create a pair from amounts of the base and the quote asset.
+    pub fn from_amounts(base: &crate::I64F64, quote: &crate::I64F64) -> Self {
+        PricePair::new_builder()
+            .base(base.into())
+            .quote(quote.into())
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+
+    #[test]
+    fn values_round_trip() {
+        let value = num("-1234.5678");
+        let packed = I64F64::from(&value);
+        assert_eq!(packed.as_slice(), value.to_le_bytes().as_slice());
+        assert_eq!(crate::I64F64::from(packed), value);
+        assert!(I64F64Reader::from_slice(&value.to_le_bytes()[1..]).is_err());
+
+        let opt = I64F64Opt::from(Some(&value));
+        assert_eq!(Option::from(opt.as_reader()), Some(value.clone()));
+        let none = I64F64Opt::from(None);
+        assert_eq!(Option::<crate::I64F64>::from(none.as_reader()), None);
+
+        let values = [num("1.5"), num("-2"), num("0.125")];
+        let vec = I64F64Vec::from(values.as_slice());
+        assert_eq!(vec.as_slice().len(), 4 + 3 * 16);
+        let reader = I64F64VecReader::from_slice(vec.as_slice()).unwrap();
+        assert_eq!(Vec::from(reader), values);
+    }
+
+    #[test]
+    fn price_pairs_work() {
+        let pair = PricePair::from_amounts(&num("4"), &num("10"));
+        let data = pair.as_slice().to_vec();
+        assert_eq!(&data[16..], num("10").to_le_bytes().as_slice());
+        let reader = PricePairReader::from_slice(&data).unwrap();
+        assert_eq!(reader.price().unwrap(), num("2.5"));
+        assert_eq!(crate::I64F64::from(reader.base()), num("4"));
+
+        let empty = PricePair::from_amounts(&num("0"), &num("1"));
+        assert!(empty.as_reader().price().is_err());
+
+        let pairs = PricePairVec::new_builder().push(pair).push(empty).build();
+        let reader = PricePairVecReader::from_slice(pairs.as_slice()).unwrap();
+        assert_eq!(reader.len(), 2);
+        assert_eq!(reader.get(0).unwrap().price().unwrap(), num("2.5"));
+    }
+}
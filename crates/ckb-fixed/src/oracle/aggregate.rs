@@ -0,0 +1,324 @@
+//! Robust aggregation of reporter prices.
+//!
+//! The slice functions reorder their input in place and never allocate, so they
+//! are suitable for on-chain scripts. The `*_packed` variants take values packed
+//! as consecutive 16-byte little-endian numbers, as found in cell data, and are
+//! exported to JavaScript under the plain names taking a `Uint8Array`.
+#[cfg(feature = "alloc")]
+use crate::packed::{pack, unpack};
+use crate::{types, FixedError, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+fn empty() -> FixedError {
+    FixedError::Calculation("no values to aggregate")
+}
+
+/// Median of a sorted, non-empty slice.
+fn sorted_median(values: &[I64F64]) -> types::I64F64 {
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        values[mid].inner
+    } else {
+        values[mid - 1].inner.mean(values[mid].inner)
+    }
+}
+
+/// The exact distance between two values in units of `DELTA`, which never overflows.
+fn distance(a: types::I64F64, b: types::I64F64) -> u128 {
+    a.to_bits().abs_diff(b.to_bits())
+}
+
+/// `deviation * threshold` in units of `DELTA`, rounded down and saturating at `u128::MAX`.
+fn scale(deviation: u128, threshold: u128) -> u128 {
+    const LOW: u128 = u64::MAX as u128;
+    let (dh, dl) = (deviation >> 64, deviation & LOW);
+    let (th, tl) = (threshold >> 64, threshold & LOW);
+    // (dh * 2^64 + dl) * (th * 2^64 + tl) / 2^64, with every partial product below 2^128
+    let high = dh * th;
+    if high > LOW {
+        return u128::MAX;
+    }
+    (high << 64)
+        .checked_add(dh * tl)
+        .and_then(|r| r.checked_add(dl * th))
+        .and_then(|r| r.checked_add((dl * tl) >> 64))
+        .unwrap_or(u128::MAX)
+}
+
+/// The `k`-th smallest deviation from `center` in a sorted slice, in units of `DELTA`.
+///
+/// Deviations decrease up to `split` and increase from it, so the two runs are
+/// merged outwards from `split` instead of being collected and sorted.
+fn kth_deviation(values: &[I64F64], center: types::I64F64, split: usize, k: usize) -> u128 {
+    let (mut left, mut right) = (split, split);
+    let mut deviation = 0;
+    for _ in 0..=k {
+        let take_left = match (left.checked_sub(1), values.get(right)) {
+            (Some(l), Some(r)) => distance(center, values[l].inner) <= distance(r.inner, center),
+            (Some(_), None) => true,
+            _ => false,
+        };
+        if take_left {
+            left -= 1;
+            deviation = distance(center, values[left].inner);
+        } else {
+            deviation = distance(values[right].inner, center);
+            right += 1;
+        }
+    }
+    deviation
+}
+
+/// Calculate the median, the mean of the two middle values for an even count.
+pub fn median(values: &mut [I64F64]) -> Result<I64F64, FixedError> {
+    if values.is_empty() {
+        return Err(empty());
+    }
+    let n = values.len();
+    let (lower, upper, _) = values.select_nth_unstable(n / 2);
+    let inner = if n % 2 == 1 {
+        upper.inner
+    } else {
+        // the lower middle value is the greatest of the lower half
+        let below = lower.iter().max().unwrap();
+        below.inner.mean(upper.inner)
+    };
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the weighted median of `(value, weight)` pairs, the smallest value at which the
+/// cumulative weight reaches half of the total weight.
+pub fn weighted_median(entries: &mut [(I64F64, I64F64)]) -> Result<I64F64, FixedError> {
+    let mut total = types::I64F64::ZERO;
+    for (_, weight) in entries.iter() {
+        if weight.inner < types::I64F64::ZERO {
+            return Err(FixedError::Calculation("weight must not be negative"));
+        }
+        total = total
+            .checked_add(weight.inner)
+            .ok_or(FixedError::Calculation("weight overflow"))?;
+    }
+    if total == types::I64F64::ZERO {
+        return Err(empty());
+    }
+    entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    let half = total / 2;
+    let mut cumulative = types::I64F64::ZERO;
+    for (value, weight) in entries.iter() {
+        cumulative += weight.inner;
+        if cumulative >= half {
+            return Ok(value.clone());
+        }
+    }
+    unreachable!("the cumulative weight reaches the total")
+}
+
+/// Calculate the mean after dropping the `trim` smallest and the `trim` largest values.
+pub fn trimmed_mean(values: &mut [I64F64], trim: usize) -> Result<I64F64, FixedError> {
+    if values.len() <= trim.saturating_mul(2) {
+        return Err(empty());
+    }
+    values.sort_unstable();
+    let kept = &values[trim..values.len() - trim];
+    let mut sum = types::I64F64::ZERO;
+    for value in kept {
+        sum = sum
+            .checked_add(value.inner)
+            .ok_or(FixedError::Calculation("sum overflow"))?;
+    }
+    let inner = sum / types::I64F64::from_num(kept.len());
+    Ok(I64F64 { inner })
+}
+
+/// Drop the values further than `threshold` median absolute deviations from the median.
+///
+/// The values are sorted in place and the kept ones are returned as a subslice. When more than
+/// half of the values are equal the deviation is zero and only those values are kept.
+pub fn reject_outliers<'a>(
+    values: &'a mut [I64F64],
+    threshold: &I64F64,
+) -> Result<&'a [I64F64], FixedError> {
+    if values.is_empty() {
+        return Err(empty());
+    }
+    if threshold.inner < types::I64F64::ZERO {
+        return Err(FixedError::Calculation("threshold must not be negative"));
+    }
+    values.sort_unstable();
+    let center = sorted_median(values);
+    let split = values.partition_point(|v| v.inner < center);
+    let n = values.len();
+    let mad = if n % 2 == 1 {
+        kth_deviation(values, center, split, n / 2)
+    } else {
+        // the mean rounded towards −∞, like `I64F64::mean`
+        let lower = kth_deviation(values, center, split, n / 2 - 1);
+        let upper = kth_deviation(values, center, split, n / 2);
+        lower / 2 + upper / 2 + (lower & upper & 1)
+    };
+    let limit = scale(mad, threshold.inner.to_bits().unsigned_abs());
+    let start = values.partition_point(|v| v.inner < center && distance(center, v.inner) > limit);
+    let end = values.partition_point(|v| v.inner <= center || distance(v.inner, center) <= limit);
+    Ok(&values[start..end])
+}
+
+/// Calculate the median of packed values.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = median))]
+pub fn median_packed(values: &[u8]) -> Result<I64F64, FixedError> {
+    median(&mut unpack(values)?)
+}
+
+/// Calculate the weighted median of packed `(value, weight)` pairs.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = weightedMedian))]
+pub fn weighted_median_packed(entries: &[u8]) -> Result<I64F64, FixedError> {
+    let values = unpack(entries)?;
+    if values.len() % 2 != 0 {
+        return Err(FixedError::InvalidLength);
+    }
+    let mut entries: Vec<(I64F64, I64F64)> = values
+        .chunks_exact(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect();
+    weighted_median(&mut entries)
+}
+
+/// Calculate the trimmed mean of packed values.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = trimmedMean))]
+pub fn trimmed_mean_packed(values: &[u8], trim: usize) -> Result<I64F64, FixedError> {
+    trimmed_mean(&mut unpack(values)?, trim)
+}
+
+/// Drop the outliers from packed values, returning the kept values packed in ascending order.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = rejectOutliers))]
+pub fn reject_outliers_packed(values: &[u8], threshold: &I64F64) -> Result<Vec<u8>, FixedError> {
+    let mut values = unpack(values)?;
+    Ok(pack(reject_outliers(&mut values, threshold)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{num, nums};
+
+    #[test]
+    fn median_works() {
+        assert_eq!(median(&mut nums(&["3", "1", "2"])).unwrap(), num("2"));
+        assert_eq!(
+            median(&mut nums(&["4", "1", "3", "2"])).unwrap(),
+            num("2.5")
+        );
+        assert_eq!(median(&mut nums(&["7"])).unwrap(), num("7"));
+        assert_eq!(median(&mut nums(&["-1", "1"])).unwrap(), num("0"));
+        assert!(median(&mut []).is_err());
+    }
+
+    #[test]
+    fn weighted_median_works() {
+        let mut entries = vec![
+            (num("10"), num("1")),
+            (num("30"), num("1")),
+            (num("20"), num("3")),
+        ];
+        assert_eq!(weighted_median(&mut entries).unwrap(), num("20"));
+        let mut entries = vec![(num("10"), num("1")), (num("30"), num("1"))];
+        assert_eq!(weighted_median(&mut entries).unwrap(), num("10"));
+        let mut entries = vec![(num("10"), num("0"))];
+        assert!(weighted_median(&mut entries).is_err());
+        let mut entries = vec![(num("10"), num("-1")), (num("30"), num("2"))];
+        assert!(weighted_median(&mut entries).is_err());
+    }
+
+    #[test]
+    fn trimmed_mean_works() {
+        let mut values = nums(&["100", "1", "2", "3", "-50"]);
+        assert_eq!(trimmed_mean(&mut values, 1).unwrap(), num("2"));
+        assert_eq!(trimmed_mean(&mut values, 0).unwrap(), num("11.2"));
+        assert!(trimmed_mean(&mut values, 3).is_err());
+    }
+
+    #[test]
+    fn reject_outliers_works() {
+        let mut values = nums(&["10.1", "9.9", "10", "55", "10.2", "9.8", "0.5"]);
+        let kept = reject_outliers(&mut values, &num("3")).unwrap();
+        assert_eq!(kept, nums(&["9.8", "9.9", "10", "10.1", "10.2"]).as_slice());
+
+        let mut values = nums(&["5", "5", "5", "6"]);
+        let kept = reject_outliers(&mut values, &num("100")).unwrap();
+        assert_eq!(kept, nums(&["5", "5", "5"]).as_slice());
+
+        let mut values = nums(&["1", "2", "3", "4"]);
+        // median 2.5, deviation 1
+        let kept = reject_outliers(&mut values, &num("1")).unwrap();
+        assert_eq!(kept, nums(&["2", "3"]).as_slice());
+        let kept = reject_outliers(&mut values, &num("1.5")).unwrap();
+        assert_eq!(kept, nums(&["1", "2", "3", "4"]).as_slice());
+        assert!(reject_outliers(&mut values, &num("-1")).is_err());
+    }
+
+    #[test]
+    fn kth_deviation_matches_sorting() {
+        let mut values = nums(&["-3", "0.5", "1", "1", "2", "8", "9.5", "11"]);
+        values.sort_unstable();
+        let center = num("1.5").inner;
+        let split = values.partition_point(|v| v.inner < center);
+        let mut deviations: Vec<_> = values.iter().map(|v| (v.inner - center).abs()).collect();
+        deviations.sort_unstable();
+        for (k, expected) in deviations.iter().enumerate() {
+            let expected = expected.to_bits() as u128;
+            assert_eq!(kth_deviation(&values, center, split, k), expected);
+        }
+    }
+
+    #[test]
+    fn extreme_values_do_not_overflow() {
+        let (min, max) = (
+            I64F64::from(types::I64F64::MIN),
+            I64F64::from(types::I64F64::MAX),
+        );
+        let zero = num("0");
+        // the deviations are 0, MAX and MAX + DELTA, so the MAD is MAX and any threshold
+        // above one keeps MIN
+        let mut values = vec![min.clone(), zero.clone(), max.clone()];
+        let kept = reject_outliers(&mut values, &num("1")).unwrap();
+        assert_eq!(kept, [zero.clone(), max.clone()].as_slice());
+        let kept = reject_outliers(&mut values, &num("1.000000001")).unwrap();
+        assert_eq!(kept, [min.clone(), zero.clone(), max.clone()].as_slice());
+
+        // the median rounds down to -DELTA, leaving MAX one DELTA further than the MAD
+        let mut values = vec![min.clone(), max.clone()];
+        let kept = reject_outliers(&mut values, &num("1")).unwrap();
+        assert_eq!(kept, [min.clone()].as_slice());
+        let kept = reject_outliers(&mut values, &num("1.000000001")).unwrap();
+        assert_eq!(kept, [min.clone(), max.clone()].as_slice());
+        let kept = reject_outliers(&mut values, &num("0.5")).unwrap();
+        assert!(kept.is_empty());
+
+        let mut values = vec![min.clone(), min.clone(), zero.clone(), max.clone(), max];
+        let kept = reject_outliers(&mut values, &num("0")).unwrap();
+        assert_eq!(kept, [zero].as_slice());
+        assert_eq!(median(&mut values).unwrap(), num("0"));
+    }
+
+    #[test]
+    fn packed_works() {
+        let values = nums(&["3", "1", "2", "100"]);
+        let packed = pack(&values);
+        assert_eq!(median_packed(&packed).unwrap(), num("2.5"));
+        assert_eq!(trimmed_mean_packed(&packed, 1).unwrap(), num("2.5"));
+        let kept = reject_outliers_packed(&packed, &num("3")).unwrap();
+        assert_eq!(kept, pack(&nums(&["1", "2", "3"])));
+        assert!(median_packed(&packed[1..]).is_err());
+
+        let entries = pack(&nums(&["10", "1", "20", "3"]));
+        assert_eq!(weighted_median_packed(&entries).unwrap(), num("20"));
+        assert!(weighted_median_packed(&entries[16..]).is_err());
+        assert!(weighted_median_packed(&entries[8..]).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+//! Price oracle primitives.
+//!
+//! Oracle cells are updated on-chain and read off-chain, so like the other
+//! protocol modules everything here is exported through wasm-bindgen.
+pub mod aggregate;
+pub mod twap;
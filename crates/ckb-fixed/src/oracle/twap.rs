@@ -0,0 +1,345 @@
+//! Time-weighted average price accumulator, in the style of Uniswap v2.
+//!
+//! The accumulator sums `price * elapsed` into a [`Cumulative`], an unsigned
+//! 192-bit value with 64 fractional bits that wraps around on overflow. Readers
+//! take two observations of it and divide their wrapping difference by the
+//! elapsed time, which is correct as long as the true difference is below
+//! `2^128` in integer units. Like Uniswap's `uint256` over a 224-bit price, that
+//! bound holds by construction: prices are below `2^63` and no window is longer
+//! than `2^64` time units, so no window can wrap twice.
+//!
+//! An accumulator is stored in cell data as 48 bytes:
+//!
+//! | offset | size | field                                           |
+//! |--------|------|-------------------------------------------------|
+//! | 0      | 24   | cumulative price, little endian [`Cumulative`]  |
+//! | 24     | 16   | last price, little endian                       |
+//! | 40     | 8    | last timestamp, little endian `u64`             |
+use crate::{types, FixedError, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+/// The size of an encoded [`TwapAccumulator`].
+pub const ENCODED_LEN: usize = 48;
+
+/// The size of an encoded [`Cumulative`].
+pub const CUMULATIVE_LEN: usize = 24;
+
+/// An observation of the cumulative price, an unsigned 192-bit value with 64
+/// fractional bits that wraps around on overflow.
+///
+/// Only the difference of two observations is meaningful, see [`average_price`].
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cumulative {
+    low: u128,
+    high: u64,
+}
+
+impl Cumulative {
+    /// The cumulative value before anything is accumulated.
+    pub const ZERO: Cumulative = Cumulative { low: 0, high: 0 };
+
+    /// `price * elapsed` for a non-negative price, which always fits 191 bits
+    fn price_time(price: &I64F64, elapsed: u64) -> Cumulative {
+        let bits = price.inner.to_bits().unsigned_abs();
+        let elapsed = u128::from(elapsed);
+        let low = (bits as u64 as u128) * elapsed;
+        let high = (bits >> 64) * elapsed;
+        let (low, carry) = low.overflowing_add(high << 64);
+        Cumulative {
+            low,
+            high: (high >> 64) as u64 + u64::from(carry),
+        }
+    }
+
+    fn wrapping_add(self, other: Cumulative) -> Cumulative {
+        let (low, carry) = self.low.overflowing_add(other.low);
+        Cumulative {
+            low,
+            high: self
+                .high
+                .wrapping_add(other.high)
+                .wrapping_add(carry.into()),
+        }
+    }
+
+    fn wrapping_sub(self, other: Cumulative) -> Cumulative {
+        let (low, borrow) = self.low.overflowing_sub(other.low);
+        Cumulative {
+            low,
+            high: self
+                .high
+                .wrapping_sub(other.high)
+                .wrapping_sub(borrow.into()),
+        }
+    }
+
+    /// Divide by a positive `divisor`, `None` if the quotient needs more than 128 bits.
+    fn div_u64(self, divisor: u64) -> Option<u128> {
+        let divisor = u128::from(divisor);
+        let mut quotient = [0u128; 3];
+        let mut rem = 0u128;
+        let limbs = [self.high, (self.low >> 64) as u64, self.low as u64];
+        for (q, limb) in quotient.iter_mut().zip(limbs) {
+            let cur = (rem << 64) | u128::from(limb);
+            *q = cur / divisor;
+            rem = cur % divisor;
+        }
+        if quotient[0] != 0 {
+            return None;
+        }
+        Some((quotient[1] << 64) | quotient[2])
+    }
+
+    /// Convert to its 24 little-endian bytes without allocating.
+    pub fn to_le_array(&self) -> [u8; CUMULATIVE_LEN] {
+        let mut bytes = [0u8; CUMULATIVE_LEN];
+        bytes[..16].copy_from_slice(&self.low.to_le_bytes());
+        bytes[16..].copy_from_slice(&self.high.to_le_bytes());
+        bytes
+    }
+}
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl Cumulative {
+    /// Convert to its 24 little-endian bytes.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toLeBytes))]
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        self.to_le_array().to_vec()
+    }
+    /// Create a `Cumulative` from its 24 little-endian bytes.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromLeBytes))]
+    pub fn from_le_bytes(bytes: &[u8]) -> Result<Cumulative, FixedError> {
+        if bytes.len() != CUMULATIVE_LEN {
+            return Err(FixedError::InvalidLength);
+        }
+        Ok(Cumulative {
+            low: u128::from_le_bytes(bytes[..16].try_into().unwrap()),
+            high: u64::from_le_bytes(bytes[16..].try_into().unwrap()),
+        })
+    }
+}
+
+fn check_price(price: &I64F64) -> Result<(), FixedError> {
+    if price.inner < types::I64F64::ZERO {
+        return Err(FixedError::Calculation("price must not be negative"));
+    }
+    Ok(())
+}
+
+/// Cumulative price·time accumulator.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwapAccumulator {
+    cumulative: Cumulative,
+    last_price: I64F64,
+    last_timestamp: u64,
+}
+
+impl TwapAccumulator {
+    fn cumulative_at(&self, timestamp: u64) -> Result<Cumulative, FixedError> {
+        let elapsed = timestamp
+            .checked_sub(self.last_timestamp)
+            .ok_or(FixedError::Calculation("timestamp must not decrease"))?;
+        Ok(self
+            .cumulative
+            .wrapping_add(Cumulative::price_time(&self.last_price, elapsed)))
+    }
+}
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl TwapAccumulator {
+    /// Create an accumulator starting at `price` at `timestamp`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(constructor))]
+    pub fn new(price: &I64F64, timestamp: u64) -> Result<TwapAccumulator, FixedError> {
+        check_price(price)?;
+        Ok(TwapAccumulator {
+            cumulative: Cumulative::ZERO,
+            last_price: price.clone(),
+            last_timestamp: timestamp,
+        })
+    }
+    /// The cumulative price as of the last update.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn cumulative(&self) -> Cumulative {
+        self.cumulative
+    }
+    /// The price set by the last update.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter = lastPrice))]
+    pub fn last_price(&self) -> I64F64 {
+        self.last_price.clone()
+    }
+    /// The timestamp of the last update.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter = lastTimestamp))]
+    pub fn last_timestamp(&self) -> u64 {
+        self.last_timestamp
+    }
+    /// Accumulate the last price up to `timestamp`, then record `price` as the new price.
+    pub fn update(&mut self, price: &I64F64, timestamp: u64) -> Result<(), FixedError> {
+        check_price(price)?;
+        self.cumulative = self.cumulative_at(timestamp)?;
+        self.last_price = price.clone();
+        self.last_timestamp = timestamp;
+        Ok(())
+    }
+    /// The cumulative price at `timestamp`, assuming the last price has held since the last update.
+    pub fn observe(&self, timestamp: u64) -> Result<Cumulative, FixedError> {
+        self.cumulative_at(timestamp)
+    }
+    /// Serialize the accumulator to bytes for cell data.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toBytes))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ENCODED_LEN);
+        bytes.extend_from_slice(&self.cumulative.to_le_array());
+        bytes.extend_from_slice(&self.last_price.inner.to_le_bytes());
+        bytes.extend_from_slice(&self.last_timestamp.to_le_bytes());
+        bytes
+    }
+    /// Deserialize an accumulator from bytes produced by `to_bytes`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromBytes))]
+    pub fn from_bytes(bytes: &[u8]) -> Result<TwapAccumulator, FixedError> {
+        if bytes.len() != ENCODED_LEN {
+            return Err(FixedError::InvalidLength);
+        }
+        let last_price = I64F64::from_le_bytes(&bytes[24..40])?;
+        check_price(&last_price)?;
+        Ok(TwapAccumulator {
+            cumulative: Cumulative::from_le_bytes(&bytes[0..24])?,
+            last_price,
+            last_timestamp: u64::from_le_bytes(bytes[40..48].try_into().unwrap()),
+        })
+    }
+}
+
+/// Calculate the time-weighted average price between two observations of the cumulative price.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = averagePrice))]
+pub fn average_price(
+    start_cumulative: &Cumulative,
+    start_timestamp: u64,
+    end_cumulative: &Cumulative,
+    end_timestamp: u64,
+) -> Result<I64F64, FixedError> {
+    let elapsed = end_timestamp
+        .checked_sub(start_timestamp)
+        .filter(|e| *e > 0)
+        .ok_or(FixedError::Calculation(
+            "observations must be in increasing time order",
+        ))?;
+    let diff = end_cumulative.wrapping_sub(*start_cumulative);
+    let bits = diff
+        .div_u64(elapsed)
+        .and_then(|bits| i128::try_from(bits).ok())
+        .ok_or(FixedError::Calculation("average price overflow"))?;
+    let inner = types::I64F64::from_bits(bits);
+    Ok(I64F64 { inner })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+
+    fn cumulative(s: &str) -> Cumulative {
+        Cumulative {
+            low: num(s).inner.to_bits() as u128,
+            high: 0,
+        }
+    }
+
+    #[test]
+    fn accumulate_works() {
+        let mut acc = TwapAccumulator::new(&num("10"), 1000).unwrap();
+        let start = acc.observe(1000).unwrap();
+        assert_eq!(start, Cumulative::ZERO);
+
+        acc.update(&num("20"), 1010).unwrap();
+        assert_eq!(acc.cumulative(), cumulative("100"));
+        acc.update(&num("12.5"), 1040).unwrap();
+        assert_eq!(acc.cumulative(), cumulative("700"));
+        assert_eq!(acc.last_price(), num("12.5"));
+        assert_eq!(acc.last_timestamp(), 1040);
+
+        // 10 for 10s, 20 for 30s, 12.5 for 40s
+        let end = acc.observe(1080).unwrap();
+        assert_eq!(end, cumulative("1200"));
+        assert_eq!(average_price(&start, 1000, &end, 1080).unwrap(), num("15"));
+
+        assert!(acc.observe(1039).is_err());
+        assert!(acc.update(&num("1"), 1039).is_err());
+        assert!(acc.update(&num("-1"), 1050).is_err());
+        assert!(average_price(&start, 1000, &end, 1000).is_err());
+    }
+
+    #[test]
+    fn wrap_around() {
+        let price = num("1000000000");
+        let mut acc = TwapAccumulator::new(&price, 0).unwrap();
+        // start just below the boundary so the next update wraps
+        acc.cumulative = Cumulative::ZERO.wrapping_sub(cumulative("1000"));
+        let start = acc.observe(0).unwrap();
+        acc.update(&num("3000000000"), 1000).unwrap();
+        let end = acc.observe(2000).unwrap();
+        assert!(end.high < start.high);
+        assert_eq!(
+            average_price(&start, 0, &end, 2000).unwrap(),
+            num("2000000000")
+        );
+    }
+
+    #[test]
+    fn any_window_is_exact() {
+        // the largest price over the longest window, across a wrap
+        let price = I64F64::from(types::I64F64::MAX);
+        let mut acc = TwapAccumulator::new(&price, 0).unwrap();
+        acc.cumulative = Cumulative::ZERO.wrapping_sub(cumulative("1"));
+        let start = acc.observe(0).unwrap();
+        let end = acc.observe(u64::MAX).unwrap();
+        assert_eq!(average_price(&start, 0, &end, u64::MAX).unwrap(), price);
+
+        // large prices over millisecond timestamps, well past 2^64 in total
+        let price = num("1000000000000");
+        let acc = TwapAccumulator::new(&price, 1_700_000_000_000).unwrap();
+        let start = acc.observe(1_700_000_000_000).unwrap();
+        let end = acc.observe(1_800_000_000_000).unwrap();
+        assert_eq!(
+            average_price(&start, 1_700_000_000_000, &end, 1_800_000_000_000).unwrap(),
+            price
+        );
+
+        // an average of 2^63 or more doesn't fit the result
+        let max = Cumulative {
+            low: u128::MAX,
+            high: 0,
+        };
+        assert!(average_price(&Cumulative::ZERO, 0, &max, 1).is_err());
+        let max = Cumulative {
+            low: 0,
+            high: u64::MAX,
+        };
+        assert!(average_price(&Cumulative::ZERO, 0, &max, 2).is_err());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut acc = TwapAccumulator::new(&num("1.5"), 7).unwrap();
+        acc.update(&num("2.25"), 19).unwrap();
+        let bytes = acc.to_bytes();
+        assert_eq!(bytes.len(), ENCODED_LEN);
+        assert_eq!(&bytes[0..16], num("18").to_le_bytes().as_slice());
+        assert_eq!(&bytes[16..24], [0u8; 8].as_slice());
+        assert_eq!(&bytes[24..40], num("2.25").to_le_bytes().as_slice());
+        assert_eq!(&bytes[40..48], 19u64.to_le_bytes().as_slice());
+        assert_eq!(TwapAccumulator::from_bytes(&bytes).unwrap(), acc);
+        assert!(TwapAccumulator::from_bytes(&bytes[1..]).is_err());
+
+        let cumulative = acc.cumulative();
+        let bytes = cumulative.to_le_bytes();
+        assert_eq!(Cumulative::from_le_bytes(&bytes).unwrap(), cumulative);
+        assert!(Cumulative::from_le_bytes(&bytes[1..]).is_err());
+    }
+}
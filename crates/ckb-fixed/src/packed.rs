@@ -0,0 +1,15 @@
+//! Arrays of numbers packed as consecutive 16-byte little-endian values.
+use crate::{FixedError, I64F64};
+use alloc::vec::Vec;
+
+/// Decode packed values, requiring the length to be a multiple of 16 bytes.
+pub(crate) fn unpack(bytes: &[u8]) -> Result<Vec<I64F64>, FixedError> {
+    if bytes.len() % 16 != 0 {
+        return Err(FixedError::InvalidLength);
+    }
+    bytes.chunks_exact(16).map(I64F64::from_le_bytes).collect()
+}
+
+pub(crate) fn pack(values: &[I64F64]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.inner.to_le_bytes()).collect()
+}
@@ -0,0 +1,230 @@
+//! Polynomials with fixed-point coefficients.
+//!
+//! Coefficients are in ascending order of degree, `c[0] + c[1] * x + c[2] * x^2 + ...`.
+//! In cell data they are packed as consecutive 16-byte little-endian values in
+//! the same order, which is what [`fit_packed`] produces and what the `*_packed`
+//! evaluators read.
+//!
+//! Evaluation uses Horner's scheme and checks every step, so a polynomial that
+//! overflows anywhere along the way is rejected instead of wrapping silently.
+#[cfg(feature = "std")]
+use crate::packed::pack;
+#[cfg(feature = "alloc")]
+use crate::packed::unpack;
+use crate::{types, FixedError, I64F64};
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+fn overflow() -> FixedError {
+    FixedError::Calculation("polynomial overflow")
+}
+
+/// Evaluate the polynomial with `coefficients` at `x`.
+pub fn evaluate(coefficients: &[I64F64], x: &I64F64) -> Result<I64F64, FixedError> {
+    let mut acc = types::I64F64::ZERO;
+    for c in coefficients.iter().rev() {
+        acc = acc
+            .checked_mul(x.inner)
+            .and_then(|r| r.checked_add(c.inner))
+            .ok_or_else(overflow)?;
+    }
+    Ok(I64F64 { inner: acc })
+}
+
+/// Evaluate the derivative of the polynomial with `coefficients` at `x`.
+pub fn evaluate_derivative(coefficients: &[I64F64], x: &I64F64) -> Result<I64F64, FixedError> {
+    let mut acc = types::I64F64::ZERO;
+    for (degree, c) in coefficients.iter().enumerate().skip(1).rev() {
+        let term = c
+            .inner
+            .checked_mul_int(degree as i128)
+            .ok_or_else(overflow)?;
+        acc = acc
+            .checked_mul(x.inner)
+            .and_then(|r| r.checked_add(term))
+            .ok_or_else(overflow)?;
+    }
+    Ok(I64F64 { inner: acc })
+}
+
+/// Evaluate the polynomial with packed `coefficients` at `x`.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = evaluatePolynomial))]
+pub fn evaluate_packed(coefficients: &[u8], x: &I64F64) -> Result<I64F64, FixedError> {
+    evaluate(&unpack(coefficients)?, x)
+}
+
+/// Evaluate the derivative of the polynomial with packed `coefficients` at `x`.
+#[cfg(feature = "alloc")]
+#[cfg_attr(
+    feature = "wasm-bindgen",
+    wasm_bindgen(js_name = evaluatePolynomialDerivative)
+)]
+pub fn evaluate_derivative_packed(coefficients: &[u8], x: &I64F64) -> Result<I64F64, FixedError> {
+    evaluate_derivative(&unpack(coefficients)?, x)
+}
+
+/// Fit a polynomial of `degree` to the points `(xs[i], ys[i])` by least squares.
+///
+/// The fit is computed off-chain in `f64` with a Householder QR decomposition of
+/// the Vandermonde matrix, so it carries `f64` rounding error and grows
+/// ill-conditioned for high degrees or wide ranges of `x`. Check the result with
+/// [`evaluate`] before storing it.
+#[cfg(feature = "std")]
+pub fn fit(xs: &[I64F64], ys: &[I64F64], degree: usize) -> Result<Vec<I64F64>, FixedError> {
+    if xs.len() != ys.len() {
+        return Err(FixedError::Calculation("series must have the same length"));
+    }
+    let (m, n) = (xs.len(), degree + 1);
+    if m < n {
+        return Err(FixedError::Calculation("not enough points for the degree"));
+    }
+    // columns of the Vandermonde matrix
+    let mut a: Vec<Vec<f64>> = Vec::with_capacity(n);
+    a.push(vec![1.0; m]);
+    for j in 1..n {
+        let column = a[j - 1]
+            .iter()
+            .zip(xs)
+            .map(|(p, x)| p * x.inner.to_num::<f64>())
+            .collect();
+        a.push(column);
+    }
+    let mut b: Vec<f64> = ys.iter().map(|y| y.inner.to_num()).collect();
+    // a column reduced to rounding noise of its original size is dependent on the previous ones
+    let tolerance: Vec<f64> = a
+        .iter()
+        .map(|column| column.iter().map(|v| v * v).sum::<f64>().sqrt() * 1.0e-12)
+        .collect();
+
+    let mut diagonal = vec![0.0; n];
+    for k in 0..n {
+        let norm = a[k][k..].iter().map(|v| v * v).sum::<f64>().sqrt();
+        if norm <= tolerance[k] {
+            return Err(FixedError::Calculation("points do not determine the fit"));
+        }
+        let alpha = if a[k][k] > 0.0 { -norm } else { norm };
+        let mut v = a[k][k..].to_vec();
+        v[0] -= alpha;
+        let vv: f64 = v.iter().map(|e| e * e).sum();
+        let reflect = |column: &mut [f64]| {
+            let s = 2.0 * v.iter().zip(column.iter()).map(|(p, q)| p * q).sum::<f64>() / vv;
+            column.iter_mut().zip(&v).for_each(|(c, e)| *c -= s * e);
+        };
+        for column in a.iter_mut().skip(k + 1) {
+            reflect(&mut column[k..]);
+        }
+        reflect(&mut b[k..]);
+        diagonal[k] = alpha;
+    }
+
+    // back substitution against the upper triangle
+    let mut coefficients = vec![0.0; n];
+    for i in (0..n).rev() {
+        let rest: f64 = (i + 1..n).map(|j| a[j][i] * coefficients[j]).sum();
+        coefficients[i] = (b[i] - rest) / diagonal[i];
+    }
+    coefficients
+        .into_iter()
+        .map(|c| {
+            types::I64F64::checked_from_num(c)
+                .map(I64F64::from)
+                .ok_or(FixedError::Calculation("coefficient out of range"))
+        })
+        .collect()
+}
+
+/// Fit a polynomial of `degree` to two packed series by least squares, returning the packed
+/// coefficients.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fitPolynomial))]
+pub fn fit_packed(xs: &[u8], ys: &[u8], degree: usize) -> Result<Vec<u8>, FixedError> {
+    let coefficients = fit(&unpack(xs)?, &unpack(ys)?, degree)?;
+    Ok(pack(&coefficients))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packed::pack;
+    use crate::test_util::{num, nums};
+    use approx::assert_relative_eq;
+    use fixed::traits::LossyInto;
+
+    fn to_f64(v: &I64F64) -> f64 {
+        v.inner.lossy_into()
+    }
+
+    #[test]
+    fn evaluate_works() {
+        // 1 - 2x + 0.5x^2 + 0.25x^3
+        let p = nums(&["1", "-2", "0.5", "0.25"]);
+        assert_eq!(evaluate(&p, &num("2")).unwrap(), num("1"));
+        assert_eq!(evaluate(&p, &num("-1")).unwrap(), num("3.25"));
+        assert_eq!(evaluate(&p, &num("0")).unwrap(), num("1"));
+        assert_eq!(evaluate(&[], &num("3")).unwrap(), num("0"));
+
+        // -2 + x + 0.75x^2
+        assert_eq!(evaluate_derivative(&p, &num("2")).unwrap(), num("3"));
+        assert_eq!(evaluate_derivative(&p, &num("0")).unwrap(), num("-2"));
+        assert_eq!(evaluate_derivative(&p[..1], &num("2")).unwrap(), num("0"));
+    }
+
+    #[test]
+    fn overflow_is_detected() {
+        let p = nums(&["0", "0", "0", "1"]);
+        assert!(evaluate(&p, &num("2097151")).is_ok());
+        assert!(evaluate(&p, &num("2097152")).is_err());
+        // the result would fit but an intermediate step does not
+        let p = nums(&["-9223372036854775807", "9223372036854775807"]);
+        assert!(evaluate(&p, &num("1")).is_ok());
+        assert!(evaluate(&p, &num("2")).is_err());
+        assert!(evaluate_derivative(&nums(&["0", "0", "9223372036854775807"]), &num("0")).is_err());
+    }
+
+    #[test]
+    fn packed_works() {
+        let p = pack(&nums(&["1", "-2", "0.5", "0.25"]));
+        assert_eq!(evaluate_packed(&p, &num("2")).unwrap(), num("1"));
+        assert_eq!(evaluate_derivative_packed(&p, &num("2")).unwrap(), num("3"));
+        assert!(evaluate_packed(&p[1..], &num("2")).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn fit_works() {
+        let expected = [0.5, -1.25, 0.125];
+        let xs: Vec<I64F64> = (0..20)
+            .map(|i| num(&format!("{}", i as f64 * 0.5)))
+            .collect();
+        let ys: Vec<I64F64> = xs
+            .iter()
+            .map(|x| {
+                let x = to_f64(x);
+                let y = expected[0] + expected[1] * x + expected[2] * x * x;
+                types::I64F64::from_num(y).into()
+            })
+            .collect();
+        let coefficients = fit(&xs, &ys, 2).unwrap();
+        assert_eq!(coefficients.len(), 3);
+        for (c, e) in coefficients.iter().zip(expected) {
+            assert_relative_eq!(to_f64(c), e, max_relative = 1.0e-12);
+        }
+
+        let bytes = fit_packed(&pack(&xs), &pack(&ys), 2).unwrap();
+        assert_eq!(bytes, pack(&coefficients));
+        let y = evaluate_packed(&bytes, &num("3")).unwrap();
+        assert_relative_eq!(to_f64(&y), -2.125, max_relative = 1.0e-12);
+
+        // a least-squares line through points that are not collinear
+        let line = fit(&nums(&["0", "1", "2"]), &nums(&["0", "1", "5"]), 1).unwrap();
+        assert_relative_eq!(to_f64(&line[0]), -0.5, max_relative = 1.0e-12);
+        assert_relative_eq!(to_f64(&line[1]), 2.5, max_relative = 1.0e-12);
+
+        assert!(fit(&xs[..2], &ys[..2], 2).is_err());
+        assert!(fit(&xs, &ys[1..], 2).is_err());
+        assert!(fit(&nums(&["1", "1", "1"]), &nums(&["0", "1", "2"]), 1).is_err());
+    }
+}
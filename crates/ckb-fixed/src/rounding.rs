@@ -0,0 +1,60 @@
+//! Directed rounding helpers shared by the protocol modules.
+//!
+//! `fixed` rounds multiplication towards −∞ and division towards zero, so for
+//! non-negative operands both round down. The helpers here provide the
+//! matching round-up variants.
+use crate::types::I64F64;
+
+/// Multiply, rounding towards +∞.
+pub(crate) fn mul_up(a: I64F64, b: I64F64) -> Option<I64F64> {
+    a.checked_neg()?.checked_mul(b)?.checked_neg()
+}
+
+/// Divide non-negative operands, rounding towards +∞.
+pub(crate) fn div_up(a: I64F64, b: I64F64) -> Option<I64F64> {
+    let q = a.checked_div(b)?;
+    if q.checked_mul(b)? == a {
+        Some(q)
+    } else {
+        q.checked_add(I64F64::DELTA)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_up_works() {
+        let half = I64F64::from_num(0.5);
+        assert_eq!(
+            mul_up(I64F64::from_bits(1), half),
+            Some(I64F64::from_bits(1))
+        );
+        assert_eq!(
+            mul_up(I64F64::from_bits(2), half),
+            Some(I64F64::from_bits(1))
+        );
+        assert_eq!(
+            mul_up(I64F64::from_num(3), half),
+            Some(I64F64::from_num(1.5))
+        );
+        assert_eq!(mul_up(I64F64::MAX, I64F64::from_num(2)), None);
+    }
+
+    #[test]
+    fn div_up_works() {
+        let three = I64F64::from_num(3);
+        assert_eq!(
+            div_up(I64F64::from_num(6), three),
+            Some(I64F64::from_num(2))
+        );
+        let down = I64F64::from_num(1) / three;
+        assert_eq!(
+            div_up(I64F64::from_num(1), three),
+            Some(down + I64F64::DELTA)
+        );
+        assert_eq!(div_up(I64F64::from_bits(1), three), Some(I64F64::DELTA));
+        assert_eq!(div_up(I64F64::from_num(1), I64F64::ZERO), None);
+    }
+}
@@ -0,0 +1,194 @@
+//! Serde support for [`I64F64`], under the `serde` feature.
+//!
+//! By default a value serializes as its decimal string, the same string
+//! `toJSON` returns in JavaScript. It is the shortest decimal that parses back
+//! to the same bits, so the round trip is exact, which a JSON number going
+//! through `f64` would not be. Deserializing from human-readable formats such
+//! as JSON also accepts integers. Binary formats such as bincode get the 16
+//! little-endian bytes of [`I64F64::to_le_array`] instead.
+//!
+//! The [`hex`] and [`bits`] modules are adapters for `#[serde(with = ...)]`:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     fee: I64F64,
+//!     #[serde(with = "ckb_fixed::serde::hex")]
+//!     price: I64F64,
+//! }
+//! ```
+use crate::{types, I64F64};
+use core::fmt;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+impl Serialize for I64F64 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&self.inner)
+        } else {
+            self.to_le_array().serialize(serializer)
+        }
+    }
+}
+
+struct DecimalVisitor;
+
+impl Visitor<'_> for DecimalVisitor {
+    type Value = I64F64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal string or an integer")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<I64F64, E> {
+        I64F64::from_str(v).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<I64F64, E> {
+        Ok(types::I64F64::from_num(v).into())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<I64F64, E> {
+        types::I64F64::checked_from_num(v)
+            .map(Into::into)
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+}
+
+impl<'de> Deserialize<'de> for I64F64 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(DecimalVisitor)
+        } else {
+            <[u8; 16]>::deserialize(deserializer).map(I64F64::from_le_array)
+        }
+    }
+}
+
+/// `0x`-prefixed hex of the 16 little-endian bytes, as in [`I64F64::to_hex`].
+pub mod hex {
+    use super::*;
+
+    /// Serialize `value` as hex.
+    pub fn serialize<S: Serializer>(value: &I64F64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_hex())
+    }
+
+    struct HexVisitor;
+
+    impl Visitor<'_> for HexVisitor {
+        type Value = I64F64;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("0x followed by 32 hex digits")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<I64F64, E> {
+            I64F64::from_hex(v).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+
+    /// Deserialize a value from hex.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<I64F64, D::Error> {
+        deserializer.deserialize_str(HexVisitor)
+    }
+}
+
+/// The raw bits as an `i128`, the value times `2^64`.
+pub mod bits {
+    use super::*;
+
+    /// Serialize the bits of `value`.
+    pub fn serialize<S: Serializer>(value: &I64F64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i128(value.inner.to_bits())
+    }
+
+    /// Deserialize a value from its bits.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<I64F64, D::Error> {
+        let bits = i128::deserialize(deserializer)?;
+        Ok(types::I64F64::from_bits(bits).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::num;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        fee: I64F64,
+        #[serde(with = "crate::serde::hex")]
+        price: I64F64,
+        #[serde(with = "crate::serde::bits")]
+        rate: I64F64,
+    }
+
+    #[test]
+    fn decimal_round_trip() {
+        for s in [
+            "0",
+            "-1.5",
+            "0.1",
+            "123456789.000000001",
+            "-9223372036854775808",
+        ] {
+            let value = num(s);
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(json, format!("\"{}\"", value.inner));
+            assert_eq!(serde_json::from_str::<I64F64>(&json).unwrap(), value);
+        }
+        let max = I64F64::from(types::I64F64::MAX);
+        let json = serde_json::to_string(&max).unwrap();
+        assert_eq!(serde_json::from_str::<I64F64>(&json).unwrap(), max);
+
+        assert_eq!(serde_json::from_str::<I64F64>("-42").unwrap(), num("-42"));
+        assert_eq!(serde_json::from_str::<I64F64>("42").unwrap(), num("42"));
+        assert!(serde_json::from_str::<I64F64>("1.5").is_err());
+        assert!(serde_json::from_str::<I64F64>("\"abc\"").is_err());
+        assert!(serde_json::from_str::<I64F64>("18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn bincode_round_trip() {
+        for s in ["0", "-1.5", "0.1", "-9223372036854775808"] {
+            let value = num(s);
+            let bytes = bincode::serialize(&value).unwrap();
+            assert_eq!(bytes, value.to_le_array());
+            assert_eq!(bincode::deserialize::<I64F64>(&bytes).unwrap(), value);
+        }
+        let max = I64F64::from(types::I64F64::MAX);
+        let bytes = bincode::serialize(&max).unwrap();
+        assert_eq!(bincode::deserialize::<I64F64>(&bytes).unwrap(), max);
+        let config = Config {
+            fee: num("0.003"),
+            price: num("1"),
+            rate: num("-0.5"),
+        };
+        let bytes = bincode::serialize(&config).unwrap();
+        assert_eq!(bincode::deserialize::<Config>(&bytes).unwrap(), config);
+        assert!(bincode::deserialize::<I64F64>(&[0; 15]).is_err());
+    }
+
+    #[test]
+    fn adapters_work() {
+        let config = Config {
+            fee: num("0.003"),
+            price: num("1"),
+            rate: num("-0.5"),
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"fee":"0.003","price":"0x00000000000000000100000000000000","rate":-9223372036854775808}"#
+        );
+        assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+
+        let bad = json.replace("0x00", "0x0g");
+        assert!(serde_json::from_str::<Config>(&bad).is_err());
+        let bad = json.replace("0x00", "00");
+        assert!(serde_json::from_str::<Config>(&bad).is_err());
+    }
+}
@@ -0,0 +1,389 @@
+//! Descriptive statistics with Welford's online algorithm.
+//!
+//! Welford's algorithm updates the mean and the sum of squared deviations one
+//! value at a time, so it neither accumulates a sum of squares that overflows
+//! `I64F64` nor loses precision to cancellation when the values share a large
+//! offset. Functions taking `sample` divide by `n - 1` when it is set and by
+//! `n` otherwise.
+//!
+//! A [`RunningStats`] accumulator is stored in cell data as 40 bytes:
+//!
+//! | offset | size | field                                    |
+//! |--------|------|------------------------------------------|
+//! | 0      | 16   | mean, little endian                      |
+//! | 16     | 16   | sum of squared deviations, little endian |
+//! | 32     | 8    | count, little endian `u64`               |
+#[cfg(feature = "alloc")]
+use crate::packed::unpack;
+use crate::{transcendental, types, FixedError, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen::prelude::*;
+
+/// The size of an encoded [`RunningStats`].
+pub const ENCODED_LEN: usize = 40;
+
+fn overflow() -> FixedError {
+    FixedError::Calculation("statistics overflow")
+}
+
+fn divisor(count: u64, sample: bool) -> Result<i128, FixedError> {
+    let divisor = if sample {
+        count.saturating_sub(1)
+    } else {
+        count
+    };
+    if divisor == 0 {
+        return Err(FixedError::Calculation("not enough values"));
+    }
+    Ok(divisor.into())
+}
+
+fn sqrt(value: types::I64F64) -> Result<types::I64F64, FixedError> {
+    transcendental::sqrt(value).map_err(|_| FixedError::Calculation("sqrt calculation failed"))
+}
+
+/// Streaming accumulator of the count, mean and variance of a series.
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RunningStats {
+    mean: types::I64F64,
+    m2: types::I64F64,
+    count: u64,
+}
+
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen)]
+impl RunningStats {
+    /// Create an empty accumulator.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(constructor))]
+    pub fn new() -> RunningStats {
+        RunningStats::default()
+    }
+    /// The number of values pushed.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(getter))]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+    /// The mean of the values pushed.
+    pub fn mean(&self) -> Result<I64F64, FixedError> {
+        divisor(self.count, false)?;
+        Ok(I64F64 { inner: self.mean })
+    }
+    /// Add a value to the series.
+    pub fn push(&mut self, value: &I64F64) -> Result<(), FixedError> {
+        let count = self.count.checked_add(1).ok_or(overflow())?;
+        let delta = value.inner.checked_sub(self.mean).ok_or(overflow())?;
+        let mean = delta
+            .checked_div_int(count.into())
+            .and_then(|d| self.mean.checked_add(d))
+            .ok_or(overflow())?;
+        let m2 = value
+            .inner
+            .checked_sub(mean)
+            .and_then(|d| d.checked_mul(delta))
+            .and_then(|d| self.m2.checked_add(d))
+            .ok_or(overflow())?;
+        *self = RunningStats { mean, m2, count };
+        Ok(())
+    }
+    /// The variance of the values pushed.
+    pub fn variance(&self, sample: bool) -> Result<I64F64, FixedError> {
+        let inner = self.m2 / divisor(self.count, sample)?;
+        Ok(I64F64 { inner })
+    }
+    /// The standard deviation of the values pushed.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = stdDev))]
+    pub fn std_dev(&self, sample: bool) -> Result<I64F64, FixedError> {
+        let inner = sqrt(self.variance(sample)?.inner)?;
+        Ok(I64F64 { inner })
+    }
+    /// Serialize the accumulator to bytes for cell data.
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = toBytes))]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ENCODED_LEN);
+        bytes.extend_from_slice(&self.mean.to_le_bytes());
+        bytes.extend_from_slice(&self.m2.to_le_bytes());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        bytes
+    }
+    /// Deserialize an accumulator from bytes produced by `to_bytes`.
+    #[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = fromBytes))]
+    pub fn from_bytes(bytes: &[u8]) -> Result<RunningStats, FixedError> {
+        if bytes.len() != ENCODED_LEN {
+            return Err(FixedError::InvalidLength);
+        }
+        let m2 = I64F64::from_le_bytes(&bytes[16..32])?.inner;
+        if m2 < types::I64F64::ZERO {
+            return Err(FixedError::Calculation(
+                "sum of squared deviations must not be negative",
+            ));
+        }
+        Ok(RunningStats {
+            mean: I64F64::from_le_bytes(&bytes[0..16])?.inner,
+            m2,
+            count: u64::from_le_bytes(bytes[32..40].try_into().unwrap()),
+        })
+    }
+}
+
+fn accumulate(values: &[I64F64]) -> Result<RunningStats, FixedError> {
+    let mut stats = RunningStats::new();
+    for value in values {
+        stats.push(value)?;
+    }
+    Ok(stats)
+}
+
+/// The sums of squared deviations of `xs` and `ys` and the sum of their co-deviations.
+fn co_moments(
+    xs: &[I64F64],
+    ys: &[I64F64],
+) -> Result<(RunningStats, RunningStats, types::I64F64), FixedError> {
+    if xs.len() != ys.len() {
+        return Err(FixedError::Calculation("series must have the same length"));
+    }
+    let (mut x_stats, mut y_stats) = (RunningStats::new(), RunningStats::new());
+    let mut c = types::I64F64::ZERO;
+    for (x, y) in xs.iter().zip(ys) {
+        let dx = x.inner.checked_sub(x_stats.mean).ok_or(overflow())?;
+        x_stats.push(x)?;
+        y_stats.push(y)?;
+        c = y
+            .inner
+            .checked_sub(y_stats.mean)
+            .and_then(|dy| dy.checked_mul(dx))
+            .and_then(|d| c.checked_add(d))
+            .ok_or(overflow())?;
+    }
+    Ok((x_stats, y_stats, c))
+}
+
+/// Calculate the mean of `values`.
+pub fn mean(values: &[I64F64]) -> Result<I64F64, FixedError> {
+    accumulate(values)?.mean()
+}
+
+/// Calculate the variance of `values`.
+pub fn variance(values: &[I64F64], sample: bool) -> Result<I64F64, FixedError> {
+    accumulate(values)?.variance(sample)
+}
+
+/// Calculate the standard deviation of `values`.
+pub fn std_dev(values: &[I64F64], sample: bool) -> Result<I64F64, FixedError> {
+    accumulate(values)?.std_dev(sample)
+}
+
+/// Calculate the covariance of the paired series `xs` and `ys`.
+pub fn covariance(xs: &[I64F64], ys: &[I64F64], sample: bool) -> Result<I64F64, FixedError> {
+    let (stats, _, c) = co_moments(xs, ys)?;
+    let inner = c / divisor(stats.count, sample)?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the Pearson correlation coefficient of the paired series `xs` and `ys`.
+pub fn correlation(xs: &[I64F64], ys: &[I64F64]) -> Result<I64F64, FixedError> {
+    let (x_stats, y_stats, c) = co_moments(xs, ys)?;
+    divisor(x_stats.count, true)?;
+    // take the roots separately, the product of the sums may not fit
+    let scale = sqrt(x_stats.m2)?
+        .checked_mul(sqrt(y_stats.m2)?)
+        .ok_or(overflow())?;
+    if scale == types::I64F64::ZERO {
+        return Err(FixedError::Calculation("series must not be constant"));
+    }
+    let inner = c
+        .checked_div(scale)
+        .ok_or(overflow())?
+        .clamp(-types::I64F64::ONE, types::I64F64::ONE);
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the Euclidean norm of `values`, the root of their sum of squares.
+pub fn norm2(values: &[I64F64]) -> Result<I64F64, FixedError> {
+    let inner = transcendental::norm_of_bits(values.iter().map(|v| v.inner))
+        .map_err(|_| FixedError::Calculation("norm2 calculation failed"))?;
+    Ok(I64F64 { inner })
+}
+
+/// Calculate the mean of packed values.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = mean))]
+pub fn mean_packed(values: &[u8]) -> Result<I64F64, FixedError> {
+    mean(&unpack(values)?)
+}
+
+/// Calculate the variance of packed values.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = variance))]
+pub fn variance_packed(values: &[u8], sample: bool) -> Result<I64F64, FixedError> {
+    variance(&unpack(values)?, sample)
+}
+
+/// Calculate the standard deviation of packed values.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = stdDev))]
+pub fn std_dev_packed(values: &[u8], sample: bool) -> Result<I64F64, FixedError> {
+    std_dev(&unpack(values)?, sample)
+}
+
+/// Calculate the covariance of two packed series.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = covariance))]
+pub fn covariance_packed(xs: &[u8], ys: &[u8], sample: bool) -> Result<I64F64, FixedError> {
+    covariance(&unpack(xs)?, &unpack(ys)?, sample)
+}
+
+/// Calculate the correlation coefficient of two packed series.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = correlation))]
+pub fn correlation_packed(xs: &[u8], ys: &[u8]) -> Result<I64F64, FixedError> {
+    correlation(&unpack(xs)?, &unpack(ys)?)
+}
+
+/// Calculate the Euclidean norm of packed values.
+#[cfg(feature = "alloc")]
+#[cfg_attr(feature = "wasm-bindgen", wasm_bindgen(js_name = norm2))]
+pub fn norm2_packed(values: &[u8]) -> Result<I64F64, FixedError> {
+    norm2(&unpack(values)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packed::pack;
+    use crate::test_util::{num, nums};
+    use approx::assert_relative_eq;
+    use fixed::traits::LossyInto;
+
+    fn to_f64(v: &I64F64) -> f64 {
+        v.inner.lossy_into()
+    }
+
+    #[test]
+    fn moments_works() {
+        let values = nums(&["2", "4", "4", "4", "5", "5", "7", "9"]);
+        // the running mean is truncated at every step, so it may be off by a few ULPs
+        let result = mean(&values).unwrap();
+        assert_relative_eq!(to_f64(&result), 5.0, max_relative = 1.0e-15);
+        let result = variance(&values, false).unwrap();
+        assert_relative_eq!(to_f64(&result), 4.0, max_relative = 1.0e-15);
+        let result = std_dev(&values, false).unwrap();
+        assert_relative_eq!(to_f64(&result), 2.0, max_relative = 1.0e-15);
+        let sample = variance(&values, true).unwrap();
+        assert_relative_eq!(to_f64(&sample), 32.0 / 7.0, max_relative = 1.0e-15);
+
+        assert!(mean(&[]).is_err());
+        assert!(variance(&values[..1], true).is_err());
+        assert_eq!(variance(&values[..1], false).unwrap(), num("0"));
+    }
+
+    #[test]
+    fn large_offset_is_stable() {
+        // a naive sum of squares would overflow here
+        let values = nums(&[
+            "4000000000.1",
+            "4000000000.2",
+            "4000000000.3",
+            "4000000000.4",
+        ]);
+        assert_relative_eq!(
+            to_f64(&mean(&values).unwrap()),
+            4000000000.25,
+            max_relative = 1.0e-15
+        );
+        let result = variance(&values, true).unwrap();
+        assert_relative_eq!(to_f64(&result), 0.05 / 3.0, max_relative = 1.0e-12);
+    }
+
+    #[test]
+    fn covariance_and_correlation_works() {
+        let xs = nums(&["1", "2", "3", "4", "5"]);
+        let ys = nums(&["2", "4", "6", "8", "10"]);
+        let zs = nums(&["5", "3", "4", "1", "2"]);
+        assert_relative_eq!(
+            to_f64(&covariance(&xs, &ys, true).unwrap()),
+            5.0,
+            max_relative = 1.0e-15
+        );
+        assert_relative_eq!(
+            to_f64(&covariance(&xs, &ys, false).unwrap()),
+            4.0,
+            max_relative = 1.0e-15
+        );
+        assert_relative_eq!(
+            to_f64(&correlation(&xs, &ys).unwrap()),
+            1.0,
+            max_relative = 1.0e-15
+        );
+        let result = correlation(&xs, &zs).unwrap();
+        assert_relative_eq!(to_f64(&result), -0.8, max_relative = 1.0e-15);
+
+        assert!(covariance(&xs, &ys[1..], true).is_err());
+        assert!(correlation(&xs, &nums(&["1", "1", "1", "1", "1"])).is_err());
+        assert!(correlation(&xs[..1], &ys[..1]).is_err());
+    }
+
+    #[test]
+    fn running_stats_works() {
+        let values = nums(&["1.5", "-2.25", "3", "10", "0.125"]);
+        let mut stats = RunningStats::new();
+        assert!(stats.mean().is_err());
+        for value in &values {
+            stats.push(value).unwrap();
+        }
+        assert_eq!(stats.count(), 5);
+        assert_eq!(stats.mean().unwrap(), mean(&values).unwrap());
+        assert_eq!(
+            stats.std_dev(true).unwrap(),
+            std_dev(&values, true).unwrap()
+        );
+
+        let bytes = stats.to_bytes();
+        assert_eq!(bytes.len(), ENCODED_LEN);
+        assert_eq!(&bytes[32..40], 5u64.to_le_bytes().as_slice());
+        let mut decoded = RunningStats::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, stats);
+        decoded.push(&num("4")).unwrap();
+        assert_eq!(decoded.count(), 6);
+        assert!(RunningStats::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn packed_works() {
+        let xs = pack(&nums(&["1", "2", "3", "4", "5"]));
+        let ys = pack(&nums(&["2", "4", "6", "8", "10"]));
+        assert_relative_eq!(
+            to_f64(&mean_packed(&xs).unwrap()),
+            3.0,
+            max_relative = 1.0e-15
+        );
+        assert_relative_eq!(
+            to_f64(&variance_packed(&xs, false).unwrap()),
+            2.0,
+            max_relative = 1.0e-15
+        );
+        assert_relative_eq!(
+            to_f64(&covariance_packed(&xs, &ys, true).unwrap()),
+            5.0,
+            max_relative = 1.0e-15
+        );
+        assert_relative_eq!(
+            to_f64(&correlation_packed(&xs, &ys).unwrap()),
+            1.0,
+            max_relative = 1.0e-15
+        );
+        assert!(std_dev_packed(&xs[1..], false).is_err());
+    }
+
+    #[test]
+    fn norm2_works() {
+        // the sum of squares is far beyond I64F64
+        let values = nums(&["3000000000", "-4000000000", "12000000000"]);
+        assert_eq!(norm2(&values).unwrap(), num("13000000000"));
+        assert_eq!(norm2_packed(&pack(&values)).unwrap(), num("13000000000"));
+        assert_eq!(norm2(&[]).unwrap(), num("0"));
+        assert!(norm2_packed(&pack(&values)[1..]).is_err());
+    }
+}
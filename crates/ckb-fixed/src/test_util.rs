@@ -0,0 +1,35 @@
+//! Helpers shared by the unit tests.
+use crate::{types, I64F64};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Parse a decimal literal.
+pub(crate) fn num(s: &str) -> I64F64 {
+    I64F64::from_str(s).unwrap()
+}
+
+/// Parse a list of decimal literals.
+#[cfg(feature = "alloc")]
+pub(crate) fn nums(values: &[&str]) -> Vec<I64F64> {
+    values.iter().map(|s| num(s)).collect()
+}
+
+/// Values of every magnitude and fraction length from a xorshift generator.
+pub(crate) fn samples(count: usize) -> impl Iterator<Item = I64F64> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..count).map(move |_| {
+        let bits = (u128::from(next()) << 64 | u128::from(next())) as i128;
+        // keep a random number of the top bits of each half
+        let (int_shift, frac_shift) = (next() % 64, next() % 64);
+        let int = (bits >> 64) >> int_shift;
+        let frac = ((bits as u64) >> frac_shift) << frac_shift;
+        let bits = (int << 64) | i128::from(frac);
+        types::I64F64::from_bits(bits).into()
+    })
+}
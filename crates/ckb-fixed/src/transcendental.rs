@@ -20,7 +20,7 @@ This module contains transcendental functions.
 use core::ops::{AddAssign, BitOrAssign, ShlAssign};
 use fixed::consts;
 use fixed::traits::{Fixed, FixedSigned, LossyFrom, ToFixed};
-use fixed::types::{I9F23, I9F55, U0F128};
+use fixed::types::{I64F64, I9F23, I9F55, U0F128};
 
 type ConstType = I9F23;
 
@@ -123,12 +123,18 @@ const ARCTAN_ANGLES: [U0F128; 64] = [
 pub enum Error {
     General,
     SqrtOnNegative,
+    /// the root doesn't fit the destination type; the name predates the
+    /// integer square root, which no longer inverts its operand
     SqrtOverflowInverting,
     LogOnNegative,
     LogOverflow,
     ExpOverflow,
     PowOverflow,
     SinOverflow,
+    ProbabilityOutOfRange,
+    RootOnNegative,
+    InvalidRootDegree,
+    NormOverflow,
 }
 
 /// right-shift with rounding
@@ -142,42 +148,69 @@ where
     //T::from_bits((x >> 1) + (x & 1))
 }
 
-/// square root
+/// square root, rounded to the nearest representable value
+///
+/// A value with bits `b` and `F` fractional bits has the root `sqrt(b << F)`
+/// in bits, so this takes the integer square root of the widened bits. Nothing
+/// is inverted or divided, which leaves no lower bound on the operand.
 pub fn sqrt<S, D>(operand: S) -> Result<D, Error>
 where
     S: Fixed + PartialOrd<ConstType>,
     D: Fixed + PartialOrd<ConstType> + From<S>,
 {
-    let mut invert = false;
     if operand < ZERO {
         return Err(Error::SqrtOnNegative);
     };
+    let operand = D::from(operand);
+    let bits: u128 = operand
+        .to_bits()
+        .try_into()
+        .map_err(|_| Error::SqrtOnNegative)?;
+    let (high, low) = match D::FRAC_NBITS {
+        0 => (0, bits),
+        128 => (bits, 0),
+        frac => (bits >> (128 - frac), bits << frac),
+    };
+    let root = isqrt_rounded(high, low).ok_or(Error::SqrtOverflowInverting)?;
+    let root = D::Bits::try_from(root).map_err(|_| Error::SqrtOverflowInverting)?;
+    Ok(D::from_bits(root))
+}
 
-    let mut operand = D::from(operand);
-    if operand == ZERO || operand == ONE {
-        return Ok(operand);
+/// integer square root of `high * 2^128 + low`, rounded to nearest
+fn isqrt_rounded(high: u128, low: u128) -> Option<u128> {
+    let len = if high != 0 {
+        256 - high.leading_zeros()
+    } else {
+        128 - low.leading_zeros()
     };
-    if operand < ONE {
-        invert = true;
-        operand = if let Some(r) = D::from_num(1).checked_div(operand) {
-            r
+    // digit by digit, one bit of the root for two bits of the radicand; the
+    // remainder stays below 2 * root + 1 but needs up to 131 bits in between,
+    // so it is kept as a (high, low) pair like the trial value
+    let mut root: u128 = 0;
+    let mut rem = (0u128, 0u128);
+    for i in (0..(len + 1) / 2).rev() {
+        let pos = 2 * i;
+        let digit = if pos >= 128 {
+            high >> (pos - 128)
         } else {
-            return Err(Error::SqrtOverflowInverting);
-        };
-    }
-    // Newton iterations
-    let mut l = (operand / D::from_num(2)) + D::from_num(1);
-    for _i in 0..D::FRAC_NBITS {
-        l = (l + operand / l) / D::from_num(2);
-    }
-    if invert {
-        l = if let Some(r) = D::from_num(1).checked_div(l) {
-            r
+            low >> pos
+        } & 3;
+        rem = ((rem.0 << 2) | (rem.1 >> 126), (rem.1 << 2) | digit);
+        let trial = (root >> 126, (root << 2) | 1);
+        if rem >= trial {
+            let (rem_low, borrow) = rem.1.overflowing_sub(trial.1);
+            rem = (rem.0 - trial.0 - borrow as u128, rem_low);
+            root = (root << 1) | 1;
         } else {
-            return Err(Error::SqrtOverflowInverting);
-        };
+            root <<= 1;
+        }
+    }
+    // the exact root is at least root + 1/2 when the remainder exceeds root
+    if rem > (0, root) {
+        root.checked_add(1)
+    } else {
+        Some(root)
     }
-    Ok(l)
 }
 
 /// base 2 logarithm assuming self >=1
@@ -363,6 +396,106 @@ where
     Ok(r)
 }
 
+/// n-th root, the real root for negative operands and odd `n`
+///
+/// Unlike [`pow`] with a fractional exponent this does not go through [`ln`],
+/// so it takes negative operands and keeps its relative precision near zero as
+/// long as `n` is below the number of integer bits. `MIN` has no positive
+/// counterpart and is treated as `-MAX`.
+pub fn root<S, D>(operand: S, n: u32) -> Result<D, Error>
+where
+    S: FixedSigned + PartialOrd<ConstType>,
+    D: FixedSigned + PartialOrd<ConstType> + From<S>,
+{
+    if n == 0 {
+        return Err(Error::InvalidRootDegree);
+    }
+    let operand = D::from(operand);
+    if n == 1 || operand == ZERO {
+        return Ok(operand);
+    }
+    if operand < ZERO {
+        if n % 2 == 0 {
+            return Err(Error::RootOnNegative);
+        }
+        return Ok(-root_positive(operand.saturating_neg(), n)?);
+    }
+    root_positive(operand, n)
+}
+
+/// cube root
+pub fn cbrt<S, D>(operand: S) -> Result<D, Error>
+where
+    S: FixedSigned + PartialOrd<ConstType>,
+    D: FixedSigned + PartialOrd<ConstType> + From<S>,
+{
+    root(operand, 3)
+}
+
+/// `base^exponent` by repeated squaring, `None` as soon as a square or product overflows
+fn checked_pow<T>(mut base: T, mut exponent: u32) -> Option<T>
+where
+    T: FixedSigned,
+{
+    let mut result = T::checked_from_num(1)?;
+    loop {
+        if exponent & 1 == 1 {
+            result = result.checked_mul(base)?;
+        }
+        exponent >>= 1;
+        if exponent == 0 {
+            return Some(result);
+        }
+        base = base.checked_mul(base)?;
+    }
+}
+
+/// n-th root of a positive operand by Newton iterations from above
+fn root_positive<T>(operand: T, n: u32) -> Result<T, Error>
+where
+    T: FixedSigned,
+{
+    let degree = T::checked_from_num(n).ok_or(Error::InvalidRootDegree)?;
+    // operand in [2^exponent, 2^(exponent + 1))
+    let leading = operand.leading_zeros();
+    let mut exponent = T::INT_NBITS as i32 - 1 - leading as i32;
+    // shift operands below one up by a multiple of n bits, as far as the sign
+    // bit allows, so that the powers in the iteration keep their precision
+    let mut shift = 0;
+    if exponent < 0 {
+        shift = exponent.unsigned_abs().div_ceil(n);
+        shift = shift.min((leading - 1) / n);
+        exponent += (shift * n) as i32;
+    }
+    let x = operand << (shift * n);
+
+    // start from 2^q * (1 + r / n) >= 2^((exponent + 1) / n), above the root
+    let q = i64::from(exponent + 1).div_euclid(i64::from(n));
+    let r = i64::from(exponent + 1).rem_euclid(i64::from(n));
+    let mantissa = T::from_num(1) + T::from_num(r) / degree;
+    let mut y = if q >= 0 {
+        mantissa << q as u32
+    } else {
+        mantissa >> q.unsigned_abs() as u32
+    };
+    // Newton iterations decrease towards the root; stop once they no longer do
+    for _i in 0..T::FRAC_NBITS {
+        // an overflowing power leaves a quotient below the resolution
+        let quotient = checked_pow(y, n - 1)
+            .and_then(|p| x.checked_div(p))
+            .unwrap_or(T::from_num(0));
+        let step = (y - quotient) / degree;
+        if step <= 0 {
+            break;
+        }
+        y -= step;
+    }
+    if shift > 0 {
+        y = (y + (T::DELTA << (shift - 1))) >> shift;
+    }
+    Ok(y)
+}
+
 /// CORDIC in rotation mode.
 fn cordic_rotation<T>(mut x: T, mut y: T, mut z: T) -> (T, T)
 where
@@ -455,12 +588,413 @@ where
     Ok(sin(angle)? / (T::from_num(1) + cos(angle)?))
 }
 
+// Range-reduced kernels for `I64F64`, shared by the functions below and by the
+// protocol modules. They return `None` on any failure, for callers to map to
+// their own error.
+
+/// ln(2)
+pub(crate) const LN_2: I64F64 = I64F64::from_bits((consts::LN_2.to_bits() >> 64) as i128);
+
+/// Base-2 logarithm of a positive operand.
+///
+/// Operands below one are shifted into `[1, 2)` first, as taking their
+/// reciprocal like [`log2`] does loses precision and overflows for the
+/// smallest values.
+pub(crate) fn log2_normalized(operand: I64F64) -> Option<I64F64> {
+    if operand <= I64F64::ZERO {
+        return None;
+    }
+    let shift = operand.leading_zeros().saturating_sub(63);
+    let log2: I64F64 = log2(operand << shift).ok()?;
+    Some(log2 - I64F64::from_num(shift))
+}
+
+/// Natural logarithm, `log2(operand) * ln(2)`.
+///
+/// [`ln`] divides by a `LOG2_E` constant that only has 23 fractional bits;
+/// this keeps all 64 bits of the `log2` result.
+pub(crate) fn ln_normalized(operand: I64F64) -> Option<I64F64> {
+    log2_normalized(operand)?.checked_mul(LN_2)
+}
+
+/// `value * 2^k`, rounding to nearest when shifting right.
+pub(crate) fn scale_pow2(value: I64F64, k: i128) -> Option<I64F64> {
+    if k >= 0 {
+        value.checked_mul_int(1i128.checked_shl(k.try_into().ok()?)?)
+    } else if k < -127 {
+        Some(I64F64::ZERO)
+    } else {
+        let shift = -k;
+        let half = 1i128 << (shift - 1);
+        Some(I64F64::from_bits(
+            value.to_bits().checked_add(half)? >> shift,
+        ))
+    }
+}
+
+/// Exponential function, `2^k * e^r` for `r = x - k * ln(2)` and integer `k`.
+///
+/// [`exp`] sums a fixed number of Taylor terms of `e^|x|`, which are truncated
+/// for large `|x|`; after the reduction `|r| <= ln(2) / 2` and the series
+/// converges to the last bit. Results below the smallest positive value are
+/// zero and results above the largest value are `None`.
+pub(crate) fn exp_reduced(x: I64F64) -> Option<I64F64> {
+    // e^-46 is below half of the smallest positive value
+    if x < I64F64::from_num(-46) {
+        return Some(I64F64::ZERO);
+    }
+    if x > I64F64::from_num(44) {
+        return None;
+    }
+    let k: i128 = (x / LN_2).round().to_num();
+    let r = x - LN_2 * k;
+    let (mut sum, mut term) = (I64F64::ONE, I64F64::ONE);
+    for i in 1.. {
+        term = term * r / i;
+        if term == I64F64::ZERO {
+            break;
+        }
+        sum += term;
+    }
+    scale_pow2(sum, k)
+}
+
+// Gaussian functions. Unlike the generic functions above these are specific to
+// `I64F64`: the approximations need their coefficients to more bits than
+// `ConstType` holds. Everything is evaluated in fixed point with the same
+// sequence of operations on every target, so results are bit-identical on
+// RISC-V and wasm32.
+
+/// sqrt(2 * pi)
+const SQRT_2PI: I64F64 = I64F64::lit("2.5066282746310005024157652848110452530");
+/// 1 / sqrt(2 * pi)
+const FRAC_1_SQRT_2PI: I64F64 = I64F64::lit("0.3989422804014326779399460599343818685");
+/// 2 / sqrt(pi)
+const FRAC_2_SQRT_PI: I64F64 = I64F64::lit("1.1283791670955125738961589031215451717");
+/// Beyond this many standard deviations the normal tail is below the smallest positive value.
+const NORMAL_CUTOFF: I64F64 = I64F64::lit("40");
+
+/// Numerator of Hart's approximation of the normal tail, highest degree first.
+const HART_P: [I64F64; 7] = [
+    I64F64::lit("0.0352624965998911"),
+    I64F64::lit("0.700383064443688"),
+    I64F64::lit("6.37396220353165"),
+    I64F64::lit("33.912866078383"),
+    I64F64::lit("112.079291497871"),
+    I64F64::lit("221.213596169931"),
+    I64F64::lit("220.206867912376"),
+];
+/// Denominator of Hart's approximation of the normal tail, highest degree first.
+const HART_Q: [I64F64; 8] = [
+    I64F64::lit("0.0883883476483184"),
+    I64F64::lit("1.75566716318264"),
+    I64F64::lit("16.064177579207"),
+    I64F64::lit("86.7807322029461"),
+    I64F64::lit("296.564248779674"),
+    I64F64::lit("637.333633378831"),
+    I64F64::lit("793.826512519948"),
+    I64F64::lit("440.413735824752"),
+];
+/// Below this Hart's rational function is used, above it a continued fraction.
+const HART_SPLIT: I64F64 = I64F64::lit("7.07106781186547");
+
+/// Acklam's approximation of the inverse normal CDF in the central region.
+const ACKLAM_A: [I64F64; 6] = [
+    I64F64::lit("-39.69683028665376"),
+    I64F64::lit("220.9460984245205"),
+    I64F64::lit("-275.9285104469687"),
+    I64F64::lit("138.3577518672690"),
+    I64F64::lit("-30.66479806614716"),
+    I64F64::lit("2.506628277459239"),
+];
+const ACKLAM_B: [I64F64; 6] = [
+    I64F64::lit("-54.47609879822406"),
+    I64F64::lit("161.5858368580409"),
+    I64F64::lit("-155.6989798598866"),
+    I64F64::lit("66.80131188771972"),
+    I64F64::lit("-13.28068155288572"),
+    I64F64::lit("1"),
+];
+/// Acklam's approximation of the inverse normal CDF in the tails.
+const ACKLAM_C: [I64F64; 6] = [
+    I64F64::lit("-0.007784894002430293"),
+    I64F64::lit("-0.3223964580411365"),
+    I64F64::lit("-2.400758277161838"),
+    I64F64::lit("-2.549732539343734"),
+    I64F64::lit("4.374664141464968"),
+    I64F64::lit("2.938163982698783"),
+];
+const ACKLAM_D: [I64F64; 5] = [
+    I64F64::lit("0.007784695709041462"),
+    I64F64::lit("0.3224671290700398"),
+    I64F64::lit("2.445134137142996"),
+    I64F64::lit("3.754408661907416"),
+    I64F64::lit("1"),
+];
+/// Acklam's split between the central region and the tails.
+const ACKLAM_LOW: I64F64 = I64F64::lit("0.02425");
+
+/// Horner evaluation, highest degree first
+fn horner(coefficients: &[I64F64], x: I64F64) -> I64F64 {
+    coefficients.iter().fold(I64F64::ZERO, |acc, c| acc * x + c)
+}
+
+/// The upper tail of the standard normal distribution, `P(Z > x)` for `x >= 0`.
+///
+/// Uses Hart's double-precision approximation (algorithm 5666, as arranged by
+/// G. West, "Better approximations to cumulative normal functions").
+fn normal_tail(x: I64F64) -> I64F64 {
+    if x > NORMAL_CUTOFF {
+        return I64F64::ZERO;
+    }
+    let e = exp_reduced(-(x * x / 2)).unwrap_or(I64F64::ZERO);
+    if x < HART_SPLIT {
+        e * horner(&HART_P, x) / horner(&HART_Q, x)
+    } else {
+        let mut b = x + I64F64::lit("0.65");
+        for k in [4, 3, 2, 1] {
+            b = x + I64F64::from_num(k) / b;
+        }
+        e / b / SQRT_2PI
+    }
+}
+
+/// Error function.
+///
+/// The absolute error is below `1e-15`; below `0.5` a Taylor series keeps the
+/// relative error within a few units of the last place.
+pub fn erf(x: I64F64) -> I64F64 {
+    if x < I64F64::ZERO {
+        // erf is odd; MIN is clamped to -1 like any large negative input
+        return -erf(x.saturating_neg());
+    }
+    if x < I64F64::lit("0.5") {
+        // 2 / sqrt(pi) * sum of (-1)^n x^(2n + 1) / (n! (2n + 1))
+        let x2 = x * x;
+        let (mut sum, mut term) = (x, x);
+        for n in 1.. {
+            term = -(term * x2) / n;
+            let step = term / (2 * n + 1);
+            if step == I64F64::ZERO {
+                break;
+            }
+            sum += step;
+        }
+        return sum * FRAC_2_SQRT_PI;
+    }
+    I64F64::ONE - erfc(x)
+}
+
+/// Complementary error function, `1 - erf(x)`, with an absolute error below `1e-15`.
+///
+/// For large positive `x` the relative error is bounded by the approximation to
+/// about `1e-8`, until the result nears the resolution of `I64F64` (`5.4e-20`)
+/// and rounding dominates.
+pub fn erfc(x: I64F64) -> I64F64 {
+    if x < I64F64::ZERO {
+        return I64F64::from_num(2) - erfc(x.saturating_neg());
+    }
+    if x > NORMAL_CUTOFF {
+        return I64F64::ZERO;
+    }
+    normal_tail(x * I64F64::SQRT_2) * 2
+}
+
+/// Probability density function of the standard normal distribution, `e^(-x^2 / 2) / sqrt(2 * pi)`.
+pub fn normal_pdf(x: I64F64) -> I64F64 {
+    if x.unsigned_abs() > NORMAL_CUTOFF.unsigned_abs() {
+        return I64F64::ZERO;
+    }
+    exp_reduced(-(x * x / 2)).unwrap_or(I64F64::ZERO) * FRAC_1_SQRT_2PI
+}
+
+/// Cumulative distribution function of the standard normal distribution, `P(Z <= x)`, with the
+/// same accuracy as [`erfc`].
+pub fn normal_cdf(x: I64F64) -> I64F64 {
+    if x < I64F64::ZERO {
+        normal_tail(x.saturating_neg())
+    } else {
+        I64F64::ONE - normal_tail(x)
+    }
+}
+
+/// Inverse of [`normal_cdf`], the `x` at which `P(Z <= x) = p` for `0 < p < 1`.
+///
+/// Starts from Acklam's rational approximation (relative error `1.15e-9`) and
+/// refines it with one step of Halley's method against [`normal_cdf`]. The
+/// relative error is below `1e-12` for `1e-6 < p < 1 - 1e-6` and grows towards
+/// the ends as `p` itself runs out of significant bits.
+pub fn normal_inv_cdf(p: I64F64) -> Result<I64F64, Error> {
+    if p <= I64F64::ZERO || p >= I64F64::ONE {
+        return Err(Error::ProbabilityOutOfRange);
+    }
+    let q = I64F64::ONE - p;
+    let tail = |p: I64F64| -> Result<I64F64, Error> {
+        let ln = ln_normalized(p).ok_or(Error::LogOverflow)?;
+        let t: I64F64 = sqrt(-(ln * 2))?;
+        Ok(horner(&ACKLAM_C, t) / horner(&ACKLAM_D, t))
+    };
+    let mut x = if p < ACKLAM_LOW {
+        tail(p)?
+    } else if q < ACKLAM_LOW {
+        -tail(q)?
+    } else {
+        let t = p - I64F64::lit("0.5");
+        let r = t * t;
+        horner(&ACKLAM_A, r) * t / horner(&ACKLAM_B, r)
+    };
+
+    // e = cdf(x) - p, taken from the nearer tail to keep its relative precision
+    let e = if x < I64F64::ZERO {
+        normal_tail(-x) - p
+    } else {
+        q - normal_tail(x)
+    };
+    let u = exp_reduced(x * x / 2).and_then(|g| (e * SQRT_2PI).checked_mul(g));
+    if let Some(u) = u {
+        x -= u / (I64F64::ONE + x * u / 2);
+    }
+    Ok(x)
+}
+
+// Further logarithms and exponentials of `I64F64`. These build on
+// `log2_normalized` and `exp_reduced`, and switch to series
+// near the points where the naive formulas cancel.
+
+/// log10(2)
+const LOG10_2: I64F64 = I64F64::lit("0.30102999566398119521373889472449302677");
+
+/// Below this magnitude [`expm1`] and [`ln1p`] use series instead of `exp(x) - 1` and `ln(1 + x)`.
+const SERIES_LIMIT: I64F64 = I64F64::lit("0.5");
+
+/// Base-10 logarithm.
+pub fn log10(x: I64F64) -> Result<I64F64, Error> {
+    let log2 = log2_normalized(x).ok_or(Error::LogOnNegative)?;
+    Ok(log2 * LOG10_2)
+}
+
+/// Logarithm of `x` to a positive `base` other than one, `log2(x) / log2(base)`.
+pub fn log_b(x: I64F64, base: I64F64) -> Result<I64F64, Error> {
+    if base == I64F64::ONE {
+        return Err(Error::LogOverflow);
+    }
+    let log2_base = log2_normalized(base).ok_or(Error::LogOnNegative)?;
+    let log2 = log2_normalized(x).ok_or(Error::LogOnNegative)?;
+    log2.checked_div(log2_base).ok_or(Error::LogOverflow)
+}
+
+/// Base-2 exponential, `2^x`.
+///
+/// The integer part of `x` only shifts the result, so integer powers of two are
+/// exact.
+pub fn exp2(x: I64F64) -> Result<I64F64, Error> {
+    if x >= I64F64::from_num(63) {
+        return Err(Error::ExpOverflow);
+    }
+    if x < I64F64::from_num(-66) {
+        return Ok(I64F64::ZERO);
+    }
+    let k: i128 = x.floor().to_num();
+    let frac = exp_reduced(x.frac() * LN_2).ok_or(Error::ExpOverflow)?;
+    scale_pow2(frac, k).ok_or(Error::ExpOverflow)
+}
+
+/// `e^x - 1`, accurate to the last place for `x` near zero where `exp(x) - 1`
+/// would lose the low bits of the result.
+pub fn expm1(x: I64F64) -> Result<I64F64, Error> {
+    if x.unsigned_abs() >= SERIES_LIMIT.unsigned_abs() {
+        let exp = exp_reduced(x).ok_or(Error::ExpOverflow)?;
+        return Ok(exp - I64F64::ONE);
+    }
+    // sum of x^n / n! for n >= 1
+    let (mut sum, mut term) = (x, x);
+    for n in 2.. {
+        term = term * x / n;
+        if term == I64F64::ZERO {
+            break;
+        }
+        sum += term;
+    }
+    Ok(sum)
+}
+
+/// `ln(1 + x)` for `x > -1`, accurate to the last place for `x` near zero where
+/// `ln(1 + x)` would round `1 + x` first.
+pub fn ln1p(x: I64F64) -> Result<I64F64, Error> {
+    if x <= -I64F64::ONE {
+        return Err(Error::LogOnNegative);
+    }
+    if x.unsigned_abs() >= SERIES_LIMIT.unsigned_abs() {
+        // beyond MAX - 1 the added one is below the resolution of ln(x)
+        let y = x.checked_add(I64F64::ONE).unwrap_or(x);
+        return ln_normalized(y).ok_or(Error::LogOverflow);
+    }
+    // 2 * atanh(z) for z = x / (2 + x), the sum of 2 z^(2n + 1) / (2n + 1)
+    let z = x / (x + I64F64::from_num(2));
+    let z2 = z * z;
+    let (mut sum, mut term) = (z, z);
+    for n in 1.. {
+        term *= z2;
+        let step = term / (2 * n + 1);
+        if step == I64F64::ZERO {
+            break;
+        }
+        sum += step;
+    }
+    Ok(sum * 2)
+}
+
+// Euclidean norms of `I64F64` values. With bits `A` and `B` the norm
+// `sqrt(a^2 + b^2)` has the bits `sqrt(A^2 + B^2)`, so the squares are summed
+// as 256-bit integers and the root taken with the same integer square root as
+// [`sqrt`]. Nothing overflows unless the norm itself does, and the result is
+// correctly rounded. CORDIC vectoring as in `cordic_rotation` would avoid the
+// squares too, but only to within its iteration error and after correcting
+// for its gain.
+
+/// `x^2` as `(high, low)` 128-bit halves
+fn widening_square(x: u128) -> (u128, u128) {
+    let (high, low) = (x >> 64, x & u128::from(u64::MAX));
+    let cross = high * low;
+    // high^2 * 2^128 + cross * 2^65 + low^2
+    let (low, carry) = (low * low).overflowing_add(cross << 65);
+    (high * high + (cross >> 63) + carry as u128, low)
+}
+
+/// Root of the 256-bit sum of the squared bits of `values`.
+pub(crate) fn norm_of_bits(values: impl Iterator<Item = I64F64>) -> Result<I64F64, Error> {
+    let mut sum = (0u128, 0u128);
+    for value in values {
+        let (high, low) = widening_square(value.to_bits().unsigned_abs());
+        let (low, carry) = sum.1.overflowing_add(low);
+        let high = sum
+            .0
+            .checked_add(high)
+            .and_then(|h| h.checked_add(carry as u128))
+            .ok_or(Error::NormOverflow)?;
+        sum = (high, low);
+    }
+    let root = isqrt_rounded(sum.0, sum.1).ok_or(Error::NormOverflow)?;
+    let bits = i128::try_from(root).map_err(|_| Error::NormOverflow)?;
+    Ok(I64F64::from_bits(bits))
+}
+
+/// Length of the hypotenuse, `sqrt(a^2 + b^2)`, for any `a` and `b` whose result fits.
+pub fn hypot(a: I64F64, b: I64F64) -> Result<I64F64, Error> {
+    norm_of_bits([a, b].into_iter())
+}
+
+/// Euclidean norm of a vector, `sqrt(v[0]^2 + v[1]^2 + ...)`; zero when it is empty.
+pub fn norm2(values: &[I64F64]) -> Result<I64F64, Error> {
+    norm_of_bits(values.iter().copied())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use approx::assert_relative_eq;
     use fixed::traits::LossyInto;
-    use fixed::types::{I32F32, I64F64, U64F64};
+    use fixed::types::{I0F64, I32F32, I64F64, U64F64};
 
     #[test]
     fn sqrt_works() {
@@ -488,20 +1022,55 @@ mod tests {
     }
 
     #[test]
-    fn sqrt_check_lower_bound_of_working_values() {
-        // Todo: This could be done for other types too.
+    fn sqrt_has_no_lower_bound() {
         type S = I32F32;
         type D = I32F32;
 
-        // this works
-        let result: f64 = sqrt::<S, D>(S::from_num(5.8208e-10)).unwrap().lossy_into();
-        assert_relative_eq!(result, 0.0000261, epsilon = 1.0e-6);
+        // below 5.8205e-10 the reciprocal used to overflow; this is 2^-31
+        let result: f64 = sqrt::<S, D>(S::from_num(5.8205e-10)).unwrap().lossy_into();
+        assert_relative_eq!(result, 0.0000215792, epsilon = 1.0e-10);
+        assert_eq!(sqrt::<S, D>(S::DELTA).unwrap(), D::from_num(1) >> 16);
+        assert_eq!(sqrt::<S, D>(S::from_bits(2)).unwrap(), D::from_bits(92682));
+
+        let result: I64F64 = sqrt(I64F64::DELTA).unwrap();
+        assert_eq!(result, I64F64::from_num(1) >> 32);
 
-        // slightly below lower bound that produces an overflow
-        let res = sqrt::<S, D>(S::from_num(5.8205e-10));
+        // without integer bits the root of values close to one overflows
+        let res = sqrt::<I0F64, I0F64>(I0F64::MAX);
         assert!(matches!(res.unwrap_err(), Error::SqrtOverflowInverting));
     }
 
+    #[test]
+    fn sqrt_is_correctly_rounded() {
+        type T = I32F32;
+        // the bits of the root r of x are rounded to nearest exactly when
+        // (2r - 1)^2 <= 4 * (x << 32) < (2r + 1)^2
+        let mut bits: i64 = 1;
+        while bits < i64::MAX / 3 {
+            for x in [bits, bits + 1, bits * 2 - 1] {
+                let root: T = sqrt(T::from_bits(x)).unwrap();
+                let r = root.to_bits() as u128;
+                let n = (x as u128) << 34;
+                assert!((2 * r - 1) * (2 * r - 1) <= n, "{x}");
+                assert!(n < (2 * r + 1) * (2 * r + 1), "{x}");
+            }
+            bits = bits * 3 + 7;
+        }
+
+        let root: I64F64 = sqrt(I64F64::from_num(1u64 << 62)).unwrap();
+        assert_eq!(root, I64F64::from_num(1u64 << 31));
+        // the SQRT_2 constant is truncated, sqrt(2) * 2^64 = ...424.699
+        let root: I64F64 = sqrt(I64F64::from_num(2)).unwrap();
+        assert_eq!(root, I64F64::SQRT_2 + I64F64::DELTA);
+        for x in [1.0e-18_f64, 3.0e-7, 0.5, 7.0, 123456.789, 9.2e18] {
+            let x = I64F64::from_num(x);
+            let result: f64 = sqrt::<I64F64, I64F64>(x).unwrap().lossy_into();
+            assert_relative_eq!(result, to_f64(x).sqrt(), max_relative = 1.0e-15);
+        }
+        let root: U64F64 = sqrt(U64F64::MAX).unwrap();
+        assert_eq!(root, U64F64::from_num(1u64 << 32));
+    }
+
     #[test]
     fn rs_works() {
         let result: f64 = rs(I9F23::from_num(0)).lossy_into();
@@ -683,4 +1252,283 @@ mod tests {
         let result: f64 = tan(ONE).unwrap().lossy_into();
         assert_relative_eq!(result, 1.55741, epsilon = 1.0e-5);
     }
+    #[test]
+    fn erf_works() {
+        // (x, erf(x), erfc(x)) from the C library
+        let cases = [
+            (-3.0, -0.9999779095030014, 1.9999779095030015),
+            (-1.0, -0.8427007929497149, 1.842700792949715),
+            (-0.3, -0.3286267594591274, 1.3286267594591274),
+            (0.001, 0.0011283787909692365, 0.9988716212090307),
+            (0.1, 0.1124629160182849, 0.8875370839817152),
+            (0.4, 0.42839235504666845, 0.5716076449533315),
+            (0.5, 0.5204998778130465, 0.4795001221869535),
+            (0.8, 0.7421009647076605, 0.2578990352923395),
+            (1.5, 0.9661051464753108, 0.033894853524689274),
+            (2.0, 0.9953222650189527, 0.004677734981047265),
+            (3.0, 0.9999779095030014, 2.2090496998585438e-5),
+            (4.0, 0.9999999845827421, 1.541725790028002e-8),
+            (5.5, 0.9999999999999927, 7.357847917974398e-15),
+        ];
+        for (x, expected_erf, expected_erfc) in cases {
+            let x = I64F64::from_num(x);
+            let result: f64 = erf(x).lossy_into();
+            assert_relative_eq!(
+                result,
+                expected_erf,
+                epsilon = 1.0e-15,
+                max_relative = 1.0e-14
+            );
+            let result: f64 = erfc(x).lossy_into();
+            assert_relative_eq!(result, expected_erfc, max_relative = 1.0e-9);
+        }
+        assert_eq!(erf(I64F64::ZERO), I64F64::ZERO);
+        assert_eq!(erf(I64F64::MAX), I64F64::ONE);
+        assert_eq!(erf(I64F64::MIN), -I64F64::ONE);
+        assert_eq!(erfc(I64F64::MAX), I64F64::ZERO);
+        assert_eq!(erfc(I64F64::MIN), I64F64::from_num(2));
+    }
+
+    #[test]
+    fn normal_works() {
+        // (x, cdf(x), pdf(x))
+        let cases = [
+            (-8.0, 6.220960574271819e-16, 5.052271083536893e-15),
+            (-5.0, 2.866515718791946e-7, 1.4867195147342979e-6),
+            (-2.0, 0.02275013194817922, 0.05399096651318806),
+            (-0.5, 0.3085375387259869, 0.3520653267642995),
+            (0.0, 0.5, 0.3989422804014327),
+            (0.5, 0.6914624612740131, 0.3520653267642995),
+            (1.96, 0.9750021048517795, 0.058440944333451476),
+            (3.0, 0.9986501019683699, 0.0044318484119380075),
+            (6.0, 0.9999999990134123, 6.075882849823286e-9),
+        ];
+        for (x, expected_cdf, expected_pdf) in cases {
+            let x = I64F64::from_num(x);
+            let result: f64 = normal_cdf(x).lossy_into();
+            assert_relative_eq!(result, expected_cdf, max_relative = 1.0e-9);
+            let result: f64 = normal_pdf(x).lossy_into();
+            assert_relative_eq!(result, expected_pdf, max_relative = 1.0e-15);
+        }
+        assert_eq!(normal_cdf(I64F64::MIN), I64F64::ZERO);
+        assert_eq!(normal_cdf(I64F64::MAX), I64F64::ONE);
+        assert_eq!(normal_pdf(I64F64::MIN), I64F64::ZERO);
+        // the continued fraction takes over from the rational function here
+        let below: f64 = normal_cdf(-HART_SPLIT + I64F64::DELTA).lossy_into();
+        let above: f64 = normal_cdf(-HART_SPLIT).lossy_into();
+        assert_relative_eq!(below, above, max_relative = 1.0e-8);
+    }
+
+    #[test]
+    fn normal_inv_cdf_works() {
+        for x in [-7.0, -5.0, -2.5, -1.0, -0.01, 0.3, 1.0, 1.96, 3.5, 6.0] {
+            let p = normal_cdf(I64F64::from_num(x));
+            let result: f64 = normal_inv_cdf(p).unwrap().lossy_into();
+            assert_relative_eq!(result, x, max_relative = 1.0e-8);
+        }
+        let result: f64 = normal_inv_cdf(I64F64::from_num(0.975))
+            .unwrap()
+            .lossy_into();
+        assert_relative_eq!(result, 1.959963984540054, max_relative = 1.0e-14);
+        let result: f64 = normal_inv_cdf(I64F64::from_num(0.01)).unwrap().lossy_into();
+        assert_relative_eq!(result, -2.3263478740408408, max_relative = 1.0e-14);
+        assert_eq!(normal_inv_cdf(I64F64::lit("0.5")).unwrap(), I64F64::ZERO);
+        assert!(normal_inv_cdf(I64F64::DELTA).is_ok());
+        assert!(normal_inv_cdf(I64F64::ONE - I64F64::DELTA).is_ok());
+        assert!(normal_inv_cdf(I64F64::ZERO).is_err());
+        assert!(normal_inv_cdf(I64F64::ONE).is_err());
+    }
+
+    fn to_f64(x: I64F64) -> f64 {
+        x.lossy_into()
+    }
+
+    #[test]
+    fn log10_and_log_b_work() {
+        assert_eq!(log10(I64F64::ONE).unwrap(), I64F64::ZERO);
+        for x in [1.0e-15_f64, 0.02, 0.5, 3.0, 1000.0, 1.0e15] {
+            // compare against the value the input is rounded to
+            let x = I64F64::from_num(x);
+            let result: f64 = log10(x).unwrap().lossy_into();
+            assert_relative_eq!(result, to_f64(x).log10(), max_relative = 1.0e-15);
+        }
+        let result: f64 = log10(I64F64::DELTA).unwrap().lossy_into();
+        assert_relative_eq!(result, -64.0 * 2.0_f64.log10(), max_relative = 1.0e-15);
+        assert!(log10(I64F64::ZERO).is_err());
+        assert!(log10(-I64F64::ONE).is_err());
+
+        let result = log_b(I64F64::from_num(1024), I64F64::from_num(2)).unwrap();
+        assert_eq!(result, I64F64::from_num(10));
+        let result: f64 = log_b(I64F64::from_num(81), I64F64::from_num(3))
+            .unwrap()
+            .lossy_into();
+        assert_relative_eq!(result, 4.0, max_relative = 1.0e-15);
+        let result: f64 = log_b(I64F64::from_num(8), I64F64::from_num(0.5))
+            .unwrap()
+            .lossy_into();
+        assert_relative_eq!(result, -3.0, max_relative = 1.0e-15);
+        assert!(log_b(I64F64::from_num(8), I64F64::ONE).is_err());
+        assert!(log_b(I64F64::from_num(8), I64F64::ZERO).is_err());
+        assert!(log_b(I64F64::ZERO, I64F64::from_num(2)).is_err());
+    }
+
+    #[test]
+    fn exp2_works() {
+        assert_eq!(exp2(I64F64::ZERO).unwrap(), I64F64::ONE);
+        assert_eq!(exp2(I64F64::from_num(10)).unwrap(), I64F64::from_num(1024));
+        assert_eq!(exp2(I64F64::from_num(-64)).unwrap(), I64F64::DELTA);
+        assert_eq!(
+            exp2(I64F64::from_num(62)).unwrap(),
+            I64F64::from_num(1u64 << 62)
+        );
+        for x in [-40.25_f64, -1.5, 0.1, 0.5, 7.75, 62.5] {
+            // compare against the value the input is rounded to
+            let x = I64F64::from_num(x);
+            let result: f64 = exp2(x).unwrap().lossy_into();
+            assert_relative_eq!(result, to_f64(x).exp2(), max_relative = 1.0e-15);
+        }
+        assert_eq!(exp2(I64F64::from_num(-70)).unwrap(), I64F64::ZERO);
+        assert!(exp2(I64F64::from_num(63)).is_err());
+        assert!(exp2(I64F64::MAX).is_err());
+    }
+
+    #[test]
+    fn expm1_and_ln1p_work() {
+        assert_eq!(expm1(I64F64::ZERO).unwrap(), I64F64::ZERO);
+        assert_eq!(ln1p(I64F64::ZERO).unwrap(), I64F64::ZERO);
+        for x in [-0.75_f64, -0.3, -1.0e-6, 1.0e-9, 0.001, 0.49, 0.5, 2.0] {
+            let x = I64F64::from_num(x);
+            let result: f64 = expm1(x).unwrap().lossy_into();
+            assert_relative_eq!(result, to_f64(x).exp_m1(), max_relative = 1.0e-15);
+            let result: f64 = ln1p(x).unwrap().lossy_into();
+            assert_relative_eq!(result, to_f64(x).ln_1p(), max_relative = 1.0e-15);
+        }
+        // the low bits survive where exp(x) - 1 and ln(1 + x) lose them
+        let x = I64F64::from_num(1.0e-12);
+        let result: f64 = expm1(x).unwrap().lossy_into();
+        assert_relative_eq!(result, to_f64(x).exp_m1(), max_relative = 1.0e-7);
+        let result: f64 = ln1p(x).unwrap().lossy_into();
+        assert_relative_eq!(result, to_f64(x).ln_1p(), max_relative = 1.0e-7);
+
+        assert_eq!(expm1(I64F64::MIN).unwrap(), -I64F64::ONE);
+        assert!(expm1(I64F64::from_num(44.5)).is_err());
+        let result: f64 = ln1p(I64F64::MAX).unwrap().lossy_into();
+        assert_relative_eq!(result, 63.0 * 2.0_f64.ln(), max_relative = 1.0e-15);
+        assert!(ln1p(-I64F64::ONE).is_err());
+        assert!(ln1p(I64F64::from_num(-2)).is_err());
+    }
+
+    #[test]
+    fn root_works() {
+        let result: I64F64 = cbrt(I64F64::from_num(27)).unwrap();
+        assert_eq!(result, I64F64::from_num(3));
+        let result: I64F64 = cbrt(I64F64::from_num(-8)).unwrap();
+        assert_eq!(result, I64F64::from_num(-2));
+        let result: I64F64 = root(I64F64::from_num(1024), 10).unwrap();
+        assert_eq!(result, I64F64::from_num(2));
+        let result: I64F64 = root(I64F64::from_num(-0.03125), 5).unwrap();
+        assert_eq!(result, I64F64::from_num(-0.5));
+        for x in [1.0e-15_f64, 2.0e-6, 0.3, 2.0, 1234.5, 9.0e18] {
+            let x = I64F64::from_num(x);
+            let result: f64 = cbrt::<I64F64, I64F64>(x).unwrap().lossy_into();
+            assert_relative_eq!(result, to_f64(x).cbrt(), max_relative = 1.0e-15);
+            let result: f64 = cbrt::<I64F64, I64F64>(-x).unwrap().lossy_into();
+            assert_relative_eq!(result, -to_f64(x).cbrt(), max_relative = 1.0e-15);
+            for n in [2, 4, 7, 64] {
+                let result: f64 = root::<I64F64, I64F64>(x, n).unwrap().lossy_into();
+                let expected = to_f64(x).powf(1.0 / n as f64);
+                assert_relative_eq!(result, expected, max_relative = 1.0e-14);
+            }
+        }
+        let result: f64 = root::<I64F64, I64F64>(I64F64::from_num(1.0e18), 200)
+            .unwrap()
+            .lossy_into();
+        assert_relative_eq!(result, 1.0e18_f64.powf(0.005), max_relative = 1.0e-15);
+        let result: f64 = cbrt::<I64F64, I64F64>(I64F64::DELTA).unwrap().lossy_into();
+        assert_relative_eq!(result, 2.0_f64.powf(-64.0 / 3.0), max_relative = 1.0e-12);
+        let result: I64F64 = cbrt(I64F64::MIN).unwrap();
+        assert!(result.dist(I64F64::from_num(-(1 << 21))) <= I64F64::DELTA);
+        let x = I32F32::from_num(0.001);
+        let result: f64 = cbrt::<I32F32, I32F32>(x).unwrap().lossy_into();
+        let expected = LossyInto::<f64>::lossy_into(x).cbrt();
+        assert!((result - expected).abs() <= 2.0_f64.powi(-32));
+
+        // the Newton powers stop at the first overflow instead of running n steps; with
+        // n beyond the integer bits small operands are no longer shifted up, so they keep
+        // only the precision of the power near them
+        for x in [1.0e18_f64, 2.0, 0.5, 1.0e-15] {
+            let x = I64F64::from_num(x);
+            let result: f64 = root::<I64F64, I64F64>(x, u32::MAX).unwrap().lossy_into();
+            let expected = to_f64(x).powf(1.0 / u32::MAX as f64);
+            assert_relative_eq!(result, expected, max_relative = 1.0e-13);
+            let result: f64 = root::<I64F64, I64F64>(-x, u32::MAX).unwrap().lossy_into();
+            assert_relative_eq!(result, -expected, max_relative = 1.0e-13);
+        }
+
+        let result: I64F64 = root(I64F64::from_num(5), 1).unwrap();
+        assert_eq!(result, I64F64::from_num(5));
+        let result: I64F64 = root(I64F64::ZERO, 4).unwrap();
+        assert_eq!(result, I64F64::ZERO);
+        assert!(matches!(
+            root::<I64F64, I64F64>(I64F64::from_num(-4), 2),
+            Err(Error::RootOnNegative)
+        ));
+        assert!(matches!(
+            root::<I64F64, I64F64>(I64F64::from_num(4), 0),
+            Err(Error::InvalidRootDegree)
+        ));
+    }
+
+    #[test]
+    fn hypot_and_norm2_work() {
+        let n = |x: f64| I64F64::from_num(x);
+        assert_eq!(hypot(n(3.0), n(-4.0)).unwrap(), n(5.0));
+        assert_eq!(hypot(n(0.0), n(0.0)).unwrap(), n(0.0));
+        // squares of 2^40 overflow I64F64 but the result does not
+        let result: f64 = hypot(n(1.0e12), n(2.0e12)).unwrap().lossy_into();
+        assert_relative_eq!(result, 5.0_f64.sqrt() * 1.0e12, max_relative = 1.0e-15);
+        assert_eq!(hypot(I64F64::MAX, I64F64::ZERO).unwrap(), I64F64::MAX);
+        assert_eq!(hypot(I64F64::DELTA, I64F64::DELTA).unwrap(), I64F64::DELTA);
+        assert!(matches!(
+            hypot(I64F64::MIN, I64F64::ZERO),
+            Err(Error::NormOverflow)
+        ));
+        assert!(hypot(I64F64::MAX, I64F64::MAX).is_err());
+
+        assert_eq!(norm2(&[]).unwrap(), n(0.0));
+        assert_eq!(norm2(&[n(1.0), n(-2.0), n(2.0)]).unwrap(), n(3.0));
+        let result: f64 = norm2(&[n(3.0e9), n(-1.0e-9), n(4.0e9), n(1.0e9)])
+            .unwrap()
+            .lossy_into();
+        assert_relative_eq!(result, 26.0_f64.sqrt() * 1.0e9, max_relative = 1.0e-15);
+        assert!(norm2(&[I64F64::MAX; 5]).is_err());
+    }
+
+    #[test]
+    fn exp_reduced_works() {
+        assert_eq!(exp_reduced(I64F64::ZERO), Some(I64F64::ONE));
+        for x in [-40.0_f64, -10.5, -1.0, -0.25, 0.3, 1.0, 2.5, 20.0, 43.0] {
+            let result: f64 = exp_reduced(I64F64::from_num(x)).unwrap().lossy_into();
+            assert_relative_eq!(result, x.exp(), max_relative = 1.0e-15);
+        }
+        assert_eq!(exp_reduced(I64F64::from_num(-50)), Some(I64F64::ZERO));
+        assert_eq!(exp_reduced(I64F64::MIN), Some(I64F64::ZERO));
+        assert_eq!(exp_reduced(I64F64::from_num(44)), None);
+        assert_eq!(exp_reduced(I64F64::MAX), None);
+    }
+
+    #[test]
+    fn ln_normalized_works() {
+        assert_eq!(ln_normalized(I64F64::ONE), Some(I64F64::ZERO));
+        let result = ln_normalized(I64F64::E).unwrap();
+        assert!(result.dist(I64F64::ONE) < I64F64::from_bits(1 << 8));
+        let result = ln_normalized(I64F64::from_num(10)).unwrap();
+        assert!(result.dist(I64F64::LN_10) < I64F64::from_bits(1 << 8));
+        let result = ln_normalized(I64F64::ONE / 10).unwrap();
+        assert!(result.dist(-I64F64::LN_10) < I64F64::from_bits(1 << 8));
+        // 2^-64 has no reciprocal in range
+        let result = ln_normalized(I64F64::DELTA).unwrap();
+        assert!(result.dist(LN_2 * -64) < I64F64::from_bits(1 << 8));
+        assert_eq!(ln_normalized(I64F64::ZERO), None);
+    }
 }